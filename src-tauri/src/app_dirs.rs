@@ -0,0 +1,65 @@
+use once_cell::sync::OnceCell;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// The folders the backend keeps its files in. The app resolves them through its `AppHandle`,
+/// the CLI resolves the same folders without creating one.
+pub struct AppDirs {
+    /// Settings.
+    pub config_dir: PathBuf,
+    /// The game list cache, the quest queue and the default games root.
+    pub data_dir: PathBuf,
+    /// The runner templates and the bundled game list.
+    pub resource_dir: PathBuf,
+}
+
+static APP_DIRS: OnceCell<AppDirs> = OnceCell::new();
+
+/// Sets the folders for the rest of the run. Only the first call has an effect.
+pub fn init(dirs: AppDirs) {
+    let _ = APP_DIRS.set(dirs);
+}
+
+pub fn get() -> Result<&'static AppDirs, String> {
+    APP_DIRS
+        .get()
+        .ok_or_else(|| "The app directories are not resolved yet".to_string())
+}
+
+impl AppDirs {
+    pub fn from_handle(handle: &AppHandle) -> Result<AppDirs, String> {
+        let path = handle.path();
+        Ok(AppDirs {
+            config_dir: path
+                .app_config_dir()
+                .map_err(|e| format!("Failed to resolve app config directory: {}", e))?,
+            data_dir: path
+                .app_data_dir()
+                .map_err(|e| format!("Failed to resolve app data directory: {}", e))?,
+            resource_dir: path
+                .resource_dir()
+                .map_err(|e| format!("Failed to resolve resource directory: {}", e))?,
+        })
+    }
+
+    /// Resolves the folders the way Tauri's path resolver does for `context`, which needs no
+    /// window or event loop.
+    pub fn from_context(context: &tauri::Context) -> Result<AppDirs, String> {
+        let identifier = &context.config().identifier;
+        let config_dir = dirs::config_dir()
+            .ok_or("Failed to resolve app config directory")?
+            .join(identifier);
+        let data_dir = dirs::data_dir()
+            .ok_or("Failed to resolve app data directory")?
+            .join(identifier);
+        let resource_dir =
+            tauri::utils::platform::resource_dir(context.package_info(), &tauri::Env::default())
+                .map_err(|e| format!("Failed to resolve resource directory: {}", e))?;
+
+        Ok(AppDirs {
+            config_dir,
+            data_dir,
+            resource_dir,
+        })
+    }
+}
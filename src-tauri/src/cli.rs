@@ -0,0 +1,786 @@
+//! Runs the backend from the command line without creating a window, for machines without a
+//! desktop session: `discord-quest-completer <command> [options]`.
+
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Write};
+use std::time::Duration;
+
+use crate::app_dirs::{self, AppDirs};
+use crate::detectable::DetectableExecutable;
+use crate::engine::{self, InstalledExecutable, InstalledGame, RunnerExit};
+use crate::search::{self, SearchFilters, SearchPage};
+use crate::{gamelist, manifest, settings};
+
+const USAGE: &str = "Usage: discord-quest-completer <command> [options]
+
+Commands:
+  list                      List the installed games
+  search <query>            Search the game list by name
+        --os <os>           Only games with a win32, linux or darwin executable
+        --launcher          Only games with a launcher executable
+        --page <n>          Page of results, starting at 0
+        --limit <n>         Results per page, 20 by default
+  install <app_id>          Install a runner for a game from the game list
+        --executable <name> Install this executable instead of the first one for this OS
+  run <app_id>              Start an installed game's runner
+        --duration <time>   Stop the runner after this long, like 90s, 15m or 1h. Without it
+                            the runner keeps running until `stop`
+        --executable <name> Start this executable instead of the first one for this OS
+  stop [app_id]             Stop running runners, only those of app_id if given
+  status                    Show the running runners and where the game list is from
+
+Options:
+  --json                    Print JSON instead of tables
+  -h, --help                Show this help
+
+Without a command, the app opens its window.";
+
+const COMMANDS: &[&str] = &["list", "search", "install", "run", "stop", "status"];
+
+// Options that take a value, the rest are flags.
+const VALUE_OPTIONS: &[&str] = &["--os", "--page", "--limit", "--duration", "--executable"];
+
+/// The arguments of a command after its name.
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    /// Splits `args` into positional arguments and the options in `allowed`, `--json` is
+    /// always allowed.
+    fn parse(args: &[String], allowed: &[&str]) -> Result<Args, String> {
+        let mut parsed = Args {
+            positional: Vec::new(),
+            options: HashMap::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if !arg.starts_with("--") {
+                parsed.positional.push(arg.clone());
+                continue;
+            }
+
+            let (name, inline_value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg.as_str(), None),
+            };
+            if name != "--json" && !allowed.contains(&name) {
+                return Err(format!("Unknown option {}", name));
+            }
+            let value = if VALUE_OPTIONS.contains(&name) {
+                let value = inline_value.or_else(|| args.next().cloned());
+                Some(value.ok_or_else(|| format!("{} needs a value", name))?)
+            } else if inline_value.is_some() {
+                return Err(format!("{} does not take a value", name));
+            } else {
+                None
+            };
+            parsed.options.insert(name.to_string(), value);
+        }
+        Ok(parsed)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.contains_key(name)
+    }
+
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options.get(name).and_then(|value| value.as_deref())
+    }
+
+    fn parsed<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.value(name)
+            .map(|value| {
+                value
+                    .parse()
+                    .map_err(|_| format!("Invalid value for {}: {:?}", name, value))
+            })
+            .transpose()
+    }
+
+    fn app_id(&self) -> Result<i64, String> {
+        let app_id = self.positional.first().ok_or("Missing app_id")?;
+        app_id
+            .parse()
+            .map_err(|_| format!("Invalid app_id {:?}", app_id))
+    }
+
+    fn json(&self) -> bool {
+        self.flag("--json")
+    }
+}
+
+/// Whether `arg` is one the OS starts the app with, rather than a command. macOS passes a
+/// process serial number when the app is opened from Finder.
+fn is_launch_argument(arg: &str) -> bool {
+    arg.starts_with("-psn_")
+}
+
+/// Runs the command named by `args`, the arguments after the program name, and returns the exit
+/// code. Returns `None` when there is no command, so the app opens its window. Anything else is
+/// an unknown command, which prints the usage.
+pub fn main(args: &[String]) -> Option<i32> {
+    let (command, args) = args.split_first()?;
+    let command = command.as_str();
+    if is_launch_argument(command) {
+        return None;
+    }
+    attach_console();
+    if matches!(command, "help" | "-h" | "--help") {
+        println!("{}", USAGE);
+        return Some(0);
+    }
+    if !COMMANDS.contains(&command) {
+        eprintln!("Unknown command {:?}\n\n{}", command, USAGE);
+        return Some(2);
+    }
+
+    let mut out = redirect_logs();
+    let result = AppDirs::from_context(&crate::context()).and_then(|dirs| {
+        app_dirs::init(dirs);
+        settings::restore();
+        run_command(command, args, &mut *out)
+    });
+
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            Some(1)
+        }
+    }
+}
+
+/// Release builds on Windows are GUI programs, which start without a console. Commands print to
+/// the one of the shell they were started from instead, output redirected to a file or pipe
+/// already has somewhere to go.
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    // SAFETY: AttachConsole takes no pointers, it fails harmlessly when there is a console
+    // already or the parent has none.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// The backend logs to stdout. While a command runs those logs go to stderr instead, so stdout
+/// only carries the table or JSON it prints. Returns where that output goes.
+#[cfg(unix)]
+fn redirect_logs() -> Box<dyn Write> {
+    use std::os::fd::{AsRawFd, FromRawFd};
+
+    let stdout = io::stdout().as_raw_fd();
+    // SAFETY: `saved` is a new descriptor for the original stdout that nothing else owns.
+    unsafe {
+        let saved = libc::dup(stdout);
+        if saved < 0 {
+            return Box::new(io::stdout());
+        }
+        if libc::dup2(io::stderr().as_raw_fd(), stdout) < 0 {
+            libc::close(saved);
+            return Box::new(io::stdout());
+        }
+        Box::new(File::from_raw_fd(saved))
+    }
+}
+
+#[cfg(not(unix))]
+fn redirect_logs() -> Box<dyn Write> {
+    Box::new(io::stdout())
+}
+
+fn run_command(command: &str, args: &[String], out: &mut dyn Write) -> Result<(), String> {
+    match command {
+        "list" => list(&Args::parse(args, &[])?, out),
+        "search" => search(
+            &Args::parse(args, &["--os", "--launcher", "--page", "--limit"])?,
+            out,
+        ),
+        "install" => install(&Args::parse(args, &["--executable"])?, out),
+        "run" => run(&Args::parse(args, &["--duration", "--executable"])?, out),
+        "stop" => stop(&Args::parse(args, &[])?, out),
+        "status" => status(&Args::parse(args, &[])?, out),
+        _ => unreachable!("{} is not a command", command),
+    }
+}
+
+fn print_json(out: &mut dyn Write, value: &impl serde::Serialize) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize output: {}", e))?;
+    writeln!(out, "{}", json).map_err(|e| format!("Failed to write output: {}", e))
+}
+
+fn print_line(out: &mut dyn Write, line: &str) -> Result<(), String> {
+    writeln!(out, "{}", line).map_err(|e| format!("Failed to write output: {}", e))
+}
+
+/// Prints `rows` in columns as wide as their widest cell.
+fn print_table(out: &mut dyn Write, headers: &[&str], rows: &[Vec<String>]) -> Result<(), String> {
+    let mut widths: Vec<usize> = headers
+        .iter()
+        .map(|header| header.chars().count())
+        .collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: &mut dyn Iterator<Item = &str>| {
+        cells
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = *width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    print_line(out, &format_row(&mut headers.iter().copied()))?;
+    for row in rows {
+        print_line(out, &format_row(&mut row.iter().map(String::as_str)))?;
+    }
+    Ok(())
+}
+
+/// Parses durations like `90`, `90s`, `15m` or `1h`. A bare number is seconds.
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let (number, unit) = match text.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((index, _)) => text.split_at(index),
+        None => (text, "s"),
+    };
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        _ => return Err(format!("Invalid duration {:?}, use 90s, 15m or 1h", text)),
+    };
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid duration {:?}, use 90s, 15m or 1h", text))?;
+    if number == 0 {
+        return Err("The duration must be longer than 0".to_string());
+    }
+    Ok(Duration::from_secs(number.saturating_mul(multiplier)))
+}
+
+fn format_duration(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+fn format_age(timestamp: u64) -> String {
+    format!(
+        "{} ago",
+        format_duration(manifest::now_secs().saturating_sub(timestamp))
+    )
+}
+
+fn game_name(game: &InstalledGame) -> &str {
+    game.manifest
+        .as_ref()
+        .map(|manifest| manifest.name.as_str())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("-")
+}
+
+fn list(args: &Args, out: &mut dyn Write) -> Result<(), String> {
+    let installed = crate::installer()?.installed(crate::get_runner_manager(), None);
+    if args.json() {
+        return print_json(out, &installed);
+    }
+    if installed.is_empty() {
+        return print_line(out, "No games are installed");
+    }
+
+    let rows: Vec<Vec<String>> = installed
+        .iter()
+        .flat_map(|game| {
+            game.executables.iter().map(|executable| {
+                vec![
+                    game.app_id.to_string(),
+                    game_name(game).to_string(),
+                    executable.name.clone(),
+                    executable.os.clone().unwrap_or_else(|| "-".to_string()),
+                    match executable.pid {
+                        Some(pid) => format!("running (pid {})", pid),
+                        None => "-".to_string(),
+                    },
+                    executable
+                        .last_launched
+                        .map(format_age)
+                        .unwrap_or_else(|| "never".to_string()),
+                ]
+            })
+        })
+        .collect();
+    print_table(
+        out,
+        &[
+            "APP ID",
+            "NAME",
+            "EXECUTABLE",
+            "OS",
+            "STATUS",
+            "LAST LAUNCHED",
+        ],
+        &rows,
+    )
+}
+
+/// Loads the game list like the app does when it starts.
+fn load_game_list() -> Result<gamelist::FetchedGameList, String> {
+    let fetched = tauri::async_runtime::block_on(gamelist::resolve_configured())?;
+    for failure in &fetched.failures {
+        eprintln!(
+            "Game list source {} failed: {}",
+            failure.source, failure.error
+        );
+    }
+    Ok(fetched)
+}
+
+fn search(args: &Args, out: &mut dyn Write) -> Result<(), String> {
+    let query = args.positional.join(" ");
+    let filters = SearchFilters {
+        os: args.value("--os").map(str::to_string),
+        is_launcher: args.flag("--launcher").then_some(true),
+    };
+    let page = SearchPage {
+        number: args.parsed("--page")?.unwrap_or_default(),
+        size: args
+            .parsed("--limit")?
+            .unwrap_or(SearchPage::default().size),
+    };
+
+    load_game_list()?;
+    let results = search::search_games(query, Some(filters), Some(page));
+    if args.json() {
+        return print_json(out, &results);
+    }
+    if results.hits.is_empty() {
+        return print_line(out, "No games found");
+    }
+
+    let rows: Vec<Vec<String>> = results
+        .hits
+        .iter()
+        .map(|hit| {
+            let mut platforms: Vec<&str> = hit
+                .game
+                .executables
+                .iter()
+                .map(|executable| executable.os.as_str())
+                .collect();
+            platforms.sort_unstable();
+            platforms.dedup();
+            vec![
+                hit.game.id.clone(),
+                hit.game.name.clone(),
+                platforms.join(", "),
+                format!("{:.2}", hit.score),
+            ]
+        })
+        .collect();
+    print_table(out, &["APP ID", "NAME", "PLATFORMS", "SCORE"], &rows)?;
+
+    let first = results.page * results.page_size;
+    print_line(
+        out,
+        &format!(
+            "\nShowing {}-{} of {}",
+            first + 1,
+            first + results.hits.len(),
+            results.total
+        ),
+    )
+}
+
+/// Picks the executable `install` installs: the one named `--executable`, otherwise the first
+/// one for this OS that is not a launcher.
+fn pick_detectable_executable<'a>(
+    executables: &'a [DetectableExecutable],
+    name: Option<&str>,
+) -> Result<&'a DetectableExecutable, String> {
+    let listed = || {
+        executables
+            .iter()
+            .map(|executable| format!("{} ({})", executable.name, executable.os))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if let Some(name) = name {
+        return executables
+            .iter()
+            .find(|executable| executable.name == name)
+            .ok_or_else(|| format!("No executable named {:?}, there are: {}", name, listed()));
+    }
+
+    let mut for_this_os: Vec<&DetectableExecutable> = executables
+        .iter()
+        .filter(|executable| executable.os == engine::current_os())
+        .collect();
+    for_this_os.sort_by_key(|executable| executable.is_launcher);
+    for_this_os.first().copied().ok_or_else(|| {
+        format!(
+            "No executable for {}, pick one with --executable: {}",
+            engine::current_os(),
+            listed()
+        )
+    })
+}
+
+fn install(args: &Args, out: &mut dyn Write) -> Result<(), String> {
+    let app_id = args.app_id()?;
+    let fetched = load_game_list()?;
+    let game = fetched
+        .list
+        .games
+        .iter()
+        .find(|game| game.id == app_id.to_string())
+        .ok_or_else(|| format!("App {} is not in the game list", app_id))?;
+    let executable = pick_detectable_executable(&game.executables, args.value("--executable"))?;
+
+    let (path, executable_name) = executable.split_name();
+    let installed = crate::installer()?.install(
+        crate::get_runner_manager(),
+        path,
+        executable_name,
+        app_id,
+        Some(game.name.clone()),
+        Some(executable),
+    )?;
+
+    if args.json() {
+        return print_json(
+            out,
+            &json!({
+                "app_id": app_id,
+                "name": game.name,
+                "executable": executable.name,
+                "path": installed.path,
+                "strategy": installed.strategy,
+            }),
+        );
+    }
+    let strategy = serde_json::to_value(installed.strategy).unwrap_or_default();
+    print_line(
+        out,
+        &format!(
+            "Installed {} for {} ({}) at {:?}, {}",
+            executable.name,
+            game.name,
+            app_id,
+            installed.path,
+            strategy.as_str().unwrap_or_default()
+        ),
+    )
+}
+
+/// Picks the installed executable `run` starts, like `pick_detectable_executable`.
+fn pick_installed_executable<'a>(
+    game: &'a InstalledGame,
+    name: Option<&str>,
+) -> Result<&'a InstalledExecutable, String> {
+    if let Some(name) = name {
+        return game
+            .executables
+            .iter()
+            .find(|executable| executable.name == name)
+            .ok_or_else(|| format!("{:?} is not installed for app {}", name, game.app_id));
+    }
+
+    // Installs made before manifests existed don't know their OS, they are for this one.
+    let mut for_this_os: Vec<&InstalledExecutable> = game
+        .executables
+        .iter()
+        .filter(|executable| {
+            executable
+                .os
+                .as_deref()
+                .is_none_or(|os| os == engine::current_os())
+        })
+        .collect();
+    for_this_os.sort_by_key(|executable| executable.is_launcher.unwrap_or_default());
+    for_this_os.first().copied().ok_or_else(|| {
+        format!(
+            "App {} has no executable for {}",
+            game.app_id,
+            engine::current_os()
+        )
+    })
+}
+
+fn run(args: &Args, out: &mut dyn Write) -> Result<(), String> {
+    let app_id = args.app_id()?;
+    let duration = args.value("--duration").map(parse_duration).transpose()?;
+    let installer = crate::installer()?;
+    let game = installer
+        .installed(crate::get_runner_manager(), Some(app_id))
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("App {} is not installed, see `install`", app_id))?;
+    let executable = pick_installed_executable(&game, args.value("--executable"))?;
+    let name = match game_name(&game) {
+        "-" => executable.executable_name.clone(),
+        name => name.to_string(),
+    };
+
+    let spawned = crate::get_runner_manager().spawn(
+        &installer,
+        &name,
+        &executable.path,
+        &executable.executable_name,
+        app_id,
+    )?;
+    let pid = spawned.as_ref().map(|spawned| spawned.runner.pid);
+    eprintln!(
+        "Started {} for {} ({}){}",
+        executable.name,
+        name,
+        app_id,
+        pid.map(|pid| format!(", pid {}", pid)).unwrap_or_default()
+    );
+
+    let mut result = json!({
+        "app_id": app_id,
+        "name": name,
+        "executable": executable.name,
+        "pid": pid,
+        "duration_secs": duration.map(|duration| duration.as_secs()),
+    });
+
+    // Without a duration the runner outlives this process, it is in its own process group.
+    if let Some(duration) = duration {
+        eprintln!(
+            "Stopping it in {}, interrupting leaves it running until `stop`",
+            format_duration(duration.as_secs())
+        );
+        let (code, ended_early) = match spawned {
+            Some(spawned) => {
+                let RunnerExit { runner, code, .. } = spawned.run_for(duration)?;
+                (code, !runner.stop_requested)
+            }
+            None => {
+                std::thread::sleep(duration);
+                crate::get_runner_manager().stop_pid(None, &executable.executable_name)?;
+                (None, false)
+            }
+        };
+        result["code"] = json!(code);
+        result["ended_early"] = json!(ended_early);
+    }
+
+    if args.json() {
+        return print_json(out, &result);
+    }
+    let status = match (duration, result["ended_early"].as_bool()) {
+        (None, _) => "running".to_string(),
+        (Some(_), Some(true)) => "exited before the duration was up".to_string(),
+        (Some(duration), _) => format!("stopped after {}", format_duration(duration.as_secs())),
+    };
+    print_table(
+        out,
+        &["APP ID", "NAME", "EXECUTABLE", "PID", "STATUS"],
+        &[vec![
+            app_id.to_string(),
+            name.clone(),
+            executable.name.clone(),
+            pid.map(|pid| pid.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            status,
+        ]],
+    )
+}
+
+fn stop(args: &Args, out: &mut dyn Write) -> Result<(), String> {
+    let app_id = args.positional.first().map(|_| args.app_id()).transpose()?;
+    let runners = crate::get_runner_manager()
+        .recorded(&crate::installer()?)
+        .into_iter()
+        .map(|(runner, _)| runner)
+        .filter(|runner| app_id.is_none_or(|app_id| runner.app_id == app_id))
+        .collect();
+    let engine::StoppedRunners { stopped, errors } =
+        crate::get_runner_manager().stop_recorded(runners);
+
+    if args.json() {
+        print_json(
+            out,
+            &json!({
+                "stopped": stopped.iter().map(crate::runner_payload).collect::<Vec<_>>(),
+                "errors": errors,
+            }),
+        )?;
+    } else if stopped.is_empty() && errors.is_empty() {
+        print_line(out, "No runners are running")?;
+    } else {
+        let rows: Vec<Vec<String>> = stopped
+            .iter()
+            .map(|runner| {
+                vec![
+                    runner.pid.to_string(),
+                    runner.app_id.to_string(),
+                    runner.executable_name.clone(),
+                ]
+            })
+            .collect();
+        print_table(out, &["PID", "APP ID", "EXECUTABLE"], &rows)?;
+    }
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors.join("; ")),
+    }
+}
+
+fn status(args: &Args, out: &mut dyn Write) -> Result<(), String> {
+    let installer = crate::installer()?;
+    let games_root = installer.games_root();
+    let runners = crate::get_runner_manager().recorded(&installer);
+    let gamelist = gamelist::get_gamelist_cache_info();
+
+    if args.json() {
+        let runners: Vec<serde_json::Value> = runners
+            .iter()
+            .map(|(runner, started)| {
+                let mut payload = crate::runner_payload(runner);
+                payload["started"] = json!(started);
+                payload
+            })
+            .collect();
+        return print_json(
+            out,
+            &json!({
+                "games_root": games_root,
+                "gamelist": gamelist,
+                "runners": runners,
+            }),
+        );
+    }
+
+    print_line(out, &format!("Games root: {}", games_root.display()))?;
+    let gamelist = match gamelist {
+        Some(info) => format!("{}, checked {}", info.source, format_age(info.checked)),
+        None => "not cached yet".to_string(),
+    };
+    print_line(out, &format!("Game list: {}", gamelist))?;
+    print_line(out, "")?;
+
+    if runners.is_empty() {
+        return print_line(out, "No runners are running");
+    }
+    let rows: Vec<Vec<String>> = runners
+        .iter()
+        .map(|(runner, started)| {
+            vec![
+                runner.pid.to_string(),
+                runner.app_id.to_string(),
+                runner.executable_name.clone(),
+                format_duration(manifest::now_secs().saturating_sub(*started)),
+            ]
+        })
+        .collect();
+    print_table(out, &["PID", "APP ID", "EXECUTABLE", "RUNNING FOR"], &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn parse_error(args: &[&str], allowed: &[&str]) -> String {
+        match Args::parse(&strings(args), allowed) {
+            Ok(_) => panic!("expected {:?} to be rejected", args),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn parses_positional_arguments_flags_and_values() {
+        let args = Args::parse(
+            &strings(&["portal", "--launcher", "--os", "linux", "--limit=5"]),
+            &["--os", "--launcher", "--limit"],
+        )
+        .unwrap();
+        assert_eq!(args.positional, ["portal"]);
+        assert!(args.flag("--launcher"));
+        assert_eq!(args.value("--os"), Some("linux"));
+        assert_eq!(args.parsed::<usize>("--limit").unwrap(), Some(5));
+        assert_eq!(args.parsed::<usize>("--page").unwrap(), None);
+        assert!(!args.json());
+    }
+
+    #[test]
+    fn json_is_allowed_for_every_command() {
+        let args = Args::parse(&strings(&["--json"]), &[]).unwrap();
+        assert!(args.json());
+        assert!(Args::parse(&strings(&["--json=yes"]), &[]).is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_options_and_missing_values() {
+        let error = parse_error(&["--os", "linux"], &[]);
+        assert_eq!(error, "Unknown option --os");
+        let error = parse_error(&["--os"], &["--os"]);
+        assert_eq!(error, "--os needs a value");
+        let error = parse_error(&["--launcher=1"], &["--launcher"]);
+        assert_eq!(error, "--launcher does not take a value");
+
+        let args = Args::parse(&strings(&["--limit", "many"]), &["--limit"]).unwrap();
+        assert!(args.parsed::<usize>("--limit").is_err());
+        let args = Args::parse(&strings(&["abc"]), &[]).unwrap();
+        assert!(args.app_id().is_err());
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(
+            parse_duration(" 15m ").unwrap(),
+            Duration::from_secs(15 * 60)
+        );
+        assert_eq!(parse_duration("1h").unwrap(), Duration::from_secs(60 * 60));
+
+        assert!(parse_duration("0m").is_err());
+        assert!(parse_duration("1d").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn unknown_commands_print_the_usage() {
+        assert_eq!(main(&strings(&["bogus"])), Some(2));
+        assert_eq!(main(&strings(&["--json"])), Some(2));
+        assert_eq!(main(&strings(&["help"])), Some(0));
+    }
+
+    #[test]
+    fn launch_arguments_open_the_window() {
+        assert_eq!(main(&[]), None);
+        assert_eq!(main(&strings(&["-psn_0_12345"])), None);
+    }
+
+    #[test]
+    fn commands_reject_options_they_do_not_take() {
+        let mut out = Vec::new();
+        let error = run_command("stop", &strings(&["--duration", "5m"]), &mut out).unwrap_err();
+        assert_eq!(error, "Unknown option --duration");
+        assert!(out.is_empty());
+    }
+}
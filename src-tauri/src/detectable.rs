@@ -0,0 +1,476 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::paths;
+
+/// An application from Discord's detectable list, `GET /api/applications/detectable`.
+///
+/// Parsing is lenient: unknown fields are ignored, missing or `null` lists are empty and the id
+/// may be a string or a number. `normalize` decides whether the entry is usable.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DetectableGame {
+    #[serde(deserialize_with = "string_or_number")]
+    pub id: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub name: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub executables: Vec<DetectableExecutable>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub aliases: Vec<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub themes: Vec<String>,
+    #[serde(default)]
+    pub icon_hash: Option<String>,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub hook: bool,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub overlay: bool,
+}
+
+/// An executable entry of the detectable list. The frontend sends these without `name` when it
+/// installs one.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DetectableExecutable {
+    /// Relative to the game's folder and `/` separated, for example `bin/game.exe`.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub name: String,
+    /// `win32`, `darwin` or `linux`.
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub os: String,
+    #[serde(default, deserialize_with = "null_as_default")]
+    pub is_launcher: bool,
+}
+
+/// A detectable list after validation.
+#[derive(Default, Serialize)]
+pub struct GameList {
+    pub games: Vec<DetectableGame>,
+    /// Entries that did not parse or had no usable executable.
+    pub rejected: usize,
+}
+
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+fn string_or_number<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        String(String),
+        Number(u64),
+    }
+
+    Ok(match Id::deserialize(deserializer)? {
+        Id::String(id) => id,
+        Id::Number(id) => id.to_string(),
+    })
+}
+
+impl DetectableExecutable {
+    /// Splits the name into the folder and the file name, the arguments `create_fake_game`
+    /// takes.
+    pub fn split_name(&self) -> (&str, &str) {
+        self.name
+            .rsplit_once(['/', '\\'])
+            .unwrap_or(("", self.name.as_str()))
+    }
+
+    /// Whether a runner could be installed under this name.
+    pub fn is_installable(&self) -> bool {
+        let (path, executable_name) = self.split_name();
+        paths::relative_game_path(path).is_ok()
+            && paths::executable_file_name(executable_name).is_ok()
+    }
+}
+
+impl DetectableGame {
+    /// Trims names, drops empty or duplicate aliases and executables that can't be installed.
+    /// Returns `None` when the entry has no numeric id, no name or nothing left to install.
+    pub fn normalize(mut self) -> Option<DetectableGame> {
+        self.id = self.id.trim().to_string();
+        if self.id.parse::<i64>().is_err() {
+            return None;
+        }
+
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return None;
+        }
+
+        let mut seen = HashSet::new();
+        self.executables.retain_mut(|executable| {
+            executable.name = executable.name.trim().to_string();
+            executable.os = executable.os.trim().to_lowercase();
+            executable.is_installable()
+                && seen.insert((executable.os.clone(), executable.name.clone()))
+        });
+        if self.executables.is_empty() {
+            return None;
+        }
+
+        let mut seen = HashSet::new();
+        self.aliases = std::mem::take(&mut self.aliases)
+            .into_iter()
+            .map(|alias| alias.trim().to_string())
+            .filter(|alias| !alias.is_empty() && seen.insert(alias.clone()))
+            .collect();
+
+        Some(self)
+    }
+}
+
+/// Parses a detectable list, keeping the entries that are usable. Only a body that is not a
+/// JSON array at all is an error.
+pub fn parse_game_list(json: &str) -> Result<GameList, String> {
+    let entries: Vec<serde_json::Value> =
+        serde_json::from_str(json).map_err(|e| format!("Invalid game list: {}", e))?;
+
+    let mut list = GameList::default();
+    for entry in entries {
+        match serde_json::from_value::<DetectableGame>(entry)
+            .ok()
+            .and_then(DetectableGame::normalize)
+        {
+            Some(game) => list.games.push(game),
+            None => list.rejected += 1,
+        }
+    }
+
+    Ok(list)
+}
+
+/// A game in a `GameListDiff`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameSummary {
+    pub id: String,
+    pub name: String,
+}
+
+/// A game that is in both lists with different executables.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChangedGame {
+    pub id: String,
+    pub name: String,
+    pub added_executables: Vec<DetectableExecutable>,
+    pub removed_executables: Vec<DetectableExecutable>,
+}
+
+/// What changed between two game lists. Games are matched by id.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct GameListDiff {
+    pub added: Vec<GameSummary>,
+    pub removed: Vec<GameSummary>,
+    pub changed: Vec<ChangedGame>,
+}
+
+impl GameListDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn summary(game: &DetectableGame) -> GameSummary {
+    GameSummary {
+        id: game.id.clone(),
+        name: game.name.clone(),
+    }
+}
+
+/// The executables of `from` that are not in `other`. A changed `is_launcher` counts as a
+/// different executable.
+fn missing_executables(from: &DetectableGame, other: &DetectableGame) -> Vec<DetectableExecutable> {
+    from.executables
+        .iter()
+        .filter(|executable| {
+            !other.executables.iter().any(|candidate| {
+                candidate.name == executable.name
+                    && candidate.os == executable.os
+                    && candidate.is_launcher == executable.is_launcher
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Compares a newly fetched list against the previous one.
+pub fn diff_game_lists(previous: &[DetectableGame], current: &[DetectableGame]) -> GameListDiff {
+    let previous_by_id: HashMap<&str, &DetectableGame> = previous
+        .iter()
+        .map(|game| (game.id.as_str(), game))
+        .collect();
+    let current_ids: HashSet<&str> = current.iter().map(|game| game.id.as_str()).collect();
+
+    let mut diff = GameListDiff::default();
+    for game in current {
+        let Some(previous_game) = previous_by_id.get(game.id.as_str()) else {
+            diff.added.push(summary(game));
+            continue;
+        };
+
+        let added_executables = missing_executables(game, previous_game);
+        let removed_executables = missing_executables(previous_game, game);
+        if !added_executables.is_empty() || !removed_executables.is_empty() {
+            diff.changed.push(ChangedGame {
+                id: game.id.clone(),
+                name: game.name.clone(),
+                added_executables,
+                removed_executables,
+            });
+        }
+    }
+    diff.removed = previous
+        .iter()
+        .filter(|game| !current_ids.contains(game.id.as_str()))
+        .map(summary)
+        .collect();
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_one(entry: serde_json::Value) -> Option<DetectableGame> {
+        serde_json::from_value::<DetectableGame>(entry)
+            .ok()
+            .and_then(DetectableGame::normalize)
+    }
+
+    #[test]
+    fn accepts_numeric_ids_and_null_fields() {
+        let game = parse_one(serde_json::json!({
+            "id": 1234,
+            "name": "Game",
+            "executables": [{ "name": "game.exe", "os": "win32", "is_launcher": null }],
+            "aliases": null,
+            "themes": null,
+            "hook": null,
+        }))
+        .unwrap();
+        assert_eq!(game.id, "1234");
+        assert!(game.aliases.is_empty() && game.themes.is_empty());
+        assert!(!game.hook && !game.executables[0].is_launcher);
+    }
+
+    #[test]
+    fn trims_and_deduplicates() {
+        let game = parse_one(serde_json::json!({
+            "id": " 42 ",
+            "name": "  Game  ",
+            "executables": [
+                { "name": " bin/game.exe ", "os": "Win32" },
+                { "name": "bin/game.exe", "os": "win32" },
+                { "name": "bin/game.exe", "os": "linux" },
+            ],
+            "aliases": [" Alias ", "Alias", "", "   "],
+        }))
+        .unwrap();
+        assert_eq!(game.id, "42");
+        assert_eq!(game.name, "Game");
+        let executables: Vec<(&str, &str)> = game
+            .executables
+            .iter()
+            .map(|executable| (executable.os.as_str(), executable.name.as_str()))
+            .collect();
+        assert_eq!(
+            executables,
+            [("win32", "bin/game.exe"), ("linux", "bin/game.exe")]
+        );
+        assert_eq!(game.aliases, ["Alias"]);
+    }
+
+    #[test]
+    fn drops_executables_that_cant_be_installed() {
+        let game = parse_one(serde_json::json!({
+            "id": "1",
+            "name": "Game",
+            "executables": [
+                { "name": "../escape.exe", "os": "win32" },
+                { "name": "", "os": "win32" },
+                { "name": "bin/", "os": "linux" },
+                { "os": "linux" },
+                { "name": "game.exe", "os": "win32" },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(game.executables.len(), 1);
+        assert_eq!(game.executables[0].name, "game.exe");
+    }
+
+    #[test]
+    fn rejects_entries_without_an_id_name_or_executable() {
+        let executables = serde_json::json!([{ "name": "game.exe", "os": "win32" }]);
+        for entry in [
+            serde_json::json!({ "name": "Game", "executables": executables }),
+            serde_json::json!({ "id": "abc", "name": "Game", "executables": executables }),
+            serde_json::json!({ "id": "1", "executables": executables }),
+            serde_json::json!({ "id": "1", "name": "  ", "executables": executables }),
+            serde_json::json!({ "id": "1", "name": "Game" }),
+            serde_json::json!({ "id": "1", "name": "Game", "executables": [{ "name": "../x" }] }),
+            serde_json::json!({ "id": "1", "name": "Game", "executables": "game.exe" }),
+            serde_json::json!("not an object"),
+        ] {
+            assert!(parse_one(entry.clone()).is_none(), "{}", entry);
+        }
+    }
+
+    #[test]
+    fn parse_game_list_counts_rejected_entries() {
+        let list = parse_game_list(
+            r#"[
+                {"id": "1", "name": "Game", "executables": [{"name": "game.exe", "os": "win32"}]},
+                {"id": "2", "name": "No executables"},
+                {"id": 3, "name": "Other", "executables": [{"name": "other", "os": "linux"}]},
+                42
+            ]"#,
+        )
+        .unwrap();
+        let ids: Vec<&str> = list.games.iter().map(|game| game.id.as_str()).collect();
+        assert_eq!(ids, ["1", "3"]);
+        assert_eq!(list.rejected, 2);
+    }
+
+    #[test]
+    fn parse_game_list_needs_an_array() {
+        assert!(parse_game_list("{}").is_err());
+        assert!(parse_game_list("not json").is_err());
+        assert_eq!(parse_game_list("[]").unwrap().games.len(), 0);
+    }
+
+    #[test]
+    fn splits_executable_names() {
+        let executable = |name: &str| DetectableExecutable {
+            name: name.to_string(),
+            os: "win32".to_string(),
+            is_launcher: false,
+        };
+        assert_eq!(
+            executable("bin/win64/game.exe").split_name(),
+            ("bin/win64", "game.exe")
+        );
+        assert_eq!(
+            executable("bin\\game.exe").split_name(),
+            ("bin", "game.exe")
+        );
+        assert_eq!(executable("game.exe").split_name(), ("", "game.exe"));
+    }
+
+    fn game(id: &str, name: &str, executables: &[(&str, &str, bool)]) -> DetectableGame {
+        DetectableGame {
+            id: id.to_string(),
+            name: name.to_string(),
+            executables: executables
+                .iter()
+                .map(|(name, os, is_launcher)| DetectableExecutable {
+                    name: name.to_string(),
+                    os: os.to_string(),
+                    is_launcher: *is_launcher,
+                })
+                .collect(),
+            aliases: Vec::new(),
+            themes: Vec::new(),
+            icon_hash: None,
+            hook: false,
+            overlay: false,
+        }
+    }
+
+    fn summary_ids(games: &[GameSummary]) -> Vec<&str> {
+        games.iter().map(|game| game.id.as_str()).collect()
+    }
+
+    #[test]
+    fn diff_of_identical_lists_is_empty() {
+        let list = [game("1", "Game", &[("game.exe", "win32", false)])];
+        assert!(diff_game_lists(&list, &list).is_empty());
+        assert!(diff_game_lists(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn diff_finds_added_and_removed_games() {
+        let previous = [
+            game("1", "Kept", &[("kept.exe", "win32", false)]),
+            game("2", "Removed", &[("removed.exe", "win32", false)]),
+        ];
+        let current = [
+            game("3", "Added", &[("added.exe", "win32", false)]),
+            game("1", "Kept", &[("kept.exe", "win32", false)]),
+        ];
+
+        let diff = diff_game_lists(&previous, &current);
+        assert_eq!(summary_ids(&diff.added), ["3"]);
+        assert_eq!(diff.added[0].name, "Added");
+        assert_eq!(summary_ids(&diff.removed), ["2"]);
+        assert!(diff.changed.is_empty());
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn diff_finds_changed_executables() {
+        let previous = [game(
+            "1",
+            "Game",
+            &[
+                ("game.exe", "win32", false),
+                ("launcher.exe", "win32", false),
+                ("game", "linux", false),
+            ],
+        )];
+        // Renamed after the first download, which doesn't count as a change.
+        let current = [game(
+            "1",
+            "Game Renamed",
+            &[
+                ("game.exe", "win32", false),
+                ("launcher.exe", "win32", true),
+                ("game.x86_64", "linux", false),
+            ],
+        )];
+
+        let diff = diff_game_lists(&previous, &current);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.changed.len(), 1);
+        let changed = &diff.changed[0];
+        assert_eq!(changed.name, "Game Renamed");
+
+        let names = |executables: &[DetectableExecutable]| -> Vec<String> {
+            executables
+                .iter()
+                .map(|executable| format!("{}:{}", executable.os, executable.name))
+                .collect()
+        };
+        assert_eq!(
+            names(&changed.added_executables),
+            ["win32:launcher.exe", "linux:game.x86_64"]
+        );
+        assert_eq!(
+            names(&changed.removed_executables),
+            ["win32:launcher.exe", "linux:game"]
+        );
+        assert!(changed.added_executables[0].is_launcher);
+        assert!(!changed.removed_executables[0].is_launcher);
+    }
+
+    #[test]
+    fn diff_against_an_empty_list_adds_everything() {
+        let current = [
+            game("1", "One", &[("one.exe", "win32", false)]),
+            game("2", "Two", &[("two.exe", "win32", false)]),
+        ];
+        let diff = diff_game_lists(&[], &current);
+        assert_eq!(summary_ids(&diff.added), ["1", "2"]);
+
+        let diff = diff_game_lists(&current, &[]);
+        assert_eq!(summary_ids(&diff.removed), ["1", "2"]);
+        assert!(diff.added.is_empty() && diff.changed.is_empty());
+    }
+}
@@ -0,0 +1,830 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::runners::recorded_launches;
+use super::{RunnerManager, RunnerProcess};
+use crate::detectable::DetectableExecutable;
+use crate::install::{self, InstallMethod, InstallStrategy};
+use crate::manifest::{self, GameManifest};
+use crate::paths;
+
+/// Where a `GameInstaller` installs games and what it installs.
+#[derive(Clone)]
+pub struct InstallerConfig {
+    /// Every game gets an `<app_id>` folder in here.
+    pub games_root: PathBuf,
+    /// The runner that installed runners are copies of.
+    pub template_path: PathBuf,
+    pub template_version: String,
+    pub install_strategy: InstallStrategy,
+}
+
+/// The runner template, which installed runners are copies of.
+#[derive(Clone, Serialize)]
+pub struct RunnerTemplate {
+    pub path: PathBuf,
+    pub version: String,
+    /// SHA-256 of the template, installed copies with another hash are out of date.
+    pub hash: String,
+}
+
+/// What `GameInstaller::install` installed, and whether it was linked or copied.
+#[derive(Serialize)]
+pub struct InstalledRunner {
+    pub message: String,
+    pub path: PathBuf,
+    /// How the runner was placed, `None` when it was kept.
+    pub strategy: Option<InstallMethod>,
+    /// The runner already in place matched the template, so it was left alone.
+    pub kept: bool,
+}
+
+/// An executable found under a game's folder.
+#[derive(Serialize)]
+pub struct InstalledExecutable {
+    /// Relative to the game's folder and `/` separated, like the detectable list's names.
+    pub name: String,
+    pub executable_name: String,
+    /// The folder the executable is in, relative to the game's folder.
+    pub path: String,
+    pub full_path: PathBuf,
+    pub size: Option<u64>,
+    pub created: Option<u64>,
+    pub running: bool,
+    pub pid: Option<u32>,
+    /// From the game's manifest, missing for installs made before manifests existed.
+    pub os: Option<String>,
+    pub is_launcher: Option<bool>,
+    pub template_version: Option<String>,
+    pub template_hash: Option<String>,
+    pub last_launched: Option<u64>,
+    /// The file a runner is started from, the binary inside the bundle for macOS app bundles.
+    #[serde(skip)]
+    pub launch_path: PathBuf,
+    /// When the runner was last read from disk, which launching it does. `None` for hard links,
+    /// see `file_last_used`.
+    #[serde(skip)]
+    pub last_used: Option<SystemTime>,
+}
+
+#[derive(Serialize)]
+pub struct InstalledGame {
+    pub app_id: i64,
+    pub path: PathBuf,
+    pub executables: Vec<InstalledExecutable>,
+    pub manifest: Option<GameManifest>,
+}
+
+#[derive(Default, Serialize)]
+pub struct GcReport {
+    /// Games removed because they were not launched within the max age.
+    pub removed_games: Vec<i64>,
+    /// Runner copies removed because they were made from an older template.
+    pub removed_executables: Vec<PathBuf>,
+    pub removed_dirs: Vec<PathBuf>,
+    /// Outdated runners, or runners of expired games, that were kept because they are running.
+    pub skipped: Vec<PathBuf>,
+}
+
+/// A runner that `GameInstaller::upgrade` could not replace.
+#[derive(Serialize)]
+pub struct FailedUpgrade {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+#[derive(Default, Serialize)]
+pub struct UpgradeReport {
+    pub template_version: String,
+    pub upgraded: Vec<PathBuf>,
+    /// Outdated runners left alone because they are running.
+    pub skipped: Vec<PathBuf>,
+    pub failed: Vec<FailedUpgrade>,
+}
+
+/// The platform name the detectable list uses for this OS.
+pub fn current_os() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "win32"
+    } else if cfg!(target_os = "macos") {
+        "darwin"
+    } else {
+        "linux"
+    }
+}
+
+pub(crate) fn is_app_bundle(executable_name: &str) -> bool {
+    cfg!(target_os = "macos") && executable_name.ends_with(".app")
+}
+
+pub(crate) fn bundle_binary_name(app_name: &str) -> String {
+    app_name
+        .strip_suffix(".app")
+        .unwrap_or(app_name)
+        .to_string()
+}
+
+/// The file a runner installed at `game_folder_path`/`executable_name` is started from.
+pub(crate) fn launch_executable_path(game_folder_path: &Path, executable_name: &str) -> PathBuf {
+    if is_app_bundle(executable_name) {
+        game_folder_path
+            .join(executable_name)
+            .join("Contents/MacOS")
+            .join(bundle_binary_name(executable_name))
+    } else {
+        game_folder_path.join(executable_name)
+    }
+}
+
+/// The name the detectable list gives an executable installed at `path`/`executable_name`.
+pub(crate) fn detectable_name(path: &str, executable_name: &str) -> String {
+    let mut parts: Vec<String> = paths::relative_game_path(path)
+        .map(|relative| {
+            relative
+                .iter()
+                .map(|part| part.to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    parts.push(executable_name.to_string());
+    parts.join("/")
+}
+
+fn make_macos_app_bundle(
+    app_bundle_path: &Path,
+    app_name: &str,
+    display_name: &str,
+    runner_template: &Path,
+    strategy: InstallStrategy,
+) -> Result<(PathBuf, InstallMethod), String> {
+    let binary_name = bundle_binary_name(app_name);
+    let macos_dir = app_bundle_path.join("Contents/MacOS");
+    fs::create_dir_all(&macos_dir)
+        .map_err(|e| format!("Failed to create app bundle directories: {}", e))?;
+
+    let target_executable_path = macos_dir.join(&binary_name);
+    let method = install::place_runner(runner_template, &target_executable_path, strategy)?;
+
+    let info_plist = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>CFBundleDisplayName</key>
+    <string>{display_name}</string>
+    <key>CFBundleExecutable</key>
+    <string>{binary_name}</string>
+    <key>CFBundleIdentifier</key>
+    <string>me.markterence.discordquestcompleter.dummy</string>
+    <key>CFBundleName</key>
+    <string>{display_name}</string>
+    <key>CFBundlePackageType</key>
+    <string>APPL</string>
+    <key>CFBundleShortVersionString</key>
+    <string>1.0</string>
+    <key>CFBundleVersion</key>
+    <string>1</string>
+</dict>
+</plist>
+"#
+    );
+
+    fs::write(app_bundle_path.join("Contents/Info.plist"), info_plist)
+        .map_err(|e| format!("Failed to write Info.plist: {}", e))?;
+
+    Ok((target_executable_path, method))
+}
+
+fn unix_secs(time: std::io::Result<SystemTime>) -> Option<u64> {
+    let time = time.ok()?;
+    time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+impl InstalledExecutable {
+    fn apply_manifest(&mut self, manifest: &GameManifest) {
+        if let Some(entry) = manifest.executable(&self.name) {
+            self.os = Some(entry.os.clone());
+            self.is_launcher = Some(entry.is_launcher);
+            self.template_version = Some(entry.template_version.clone());
+            self.template_hash = Some(entry.template_hash.clone());
+        }
+        self.last_launched = manifest.last_launched_executable(&self.name);
+    }
+}
+
+fn installed_executable(
+    path: &Path,
+    relative_dir: &[String],
+    executable_name: &str,
+    runners: &RunnerManager,
+) -> InstalledExecutable {
+    let launch_path = launch_executable_path(path.parent().unwrap_or(path), executable_name);
+    let metadata = fs::metadata(&launch_path).ok();
+    let pid = runners.pid_for_path(&launch_path);
+
+    let mut name_parts = relative_dir.to_vec();
+    name_parts.push(executable_name.to_string());
+
+    InstalledExecutable {
+        name: name_parts.join("/"),
+        executable_name: executable_name.to_string(),
+        path: relative_dir.join("/"),
+        full_path: path.to_path_buf(),
+        size: metadata.as_ref().map(|metadata| metadata.len()),
+        // Not every filesystem records a creation time.
+        created: metadata.as_ref().and_then(|metadata| {
+            unix_secs(metadata.created()).or_else(|| unix_secs(metadata.modified()))
+        }),
+        running: pid.is_some(),
+        pid,
+        os: None,
+        is_launcher: None,
+        template_version: None,
+        template_hash: None,
+        last_launched: None,
+        last_used: metadata.as_ref().and_then(file_last_used),
+        launch_path,
+    }
+}
+
+/// When a runner was last read from disk. A hard linked runner shares its times with the
+/// template and every other game linked to it, so its age is unknown.
+#[cfg(unix)]
+fn file_last_used(metadata: &fs::Metadata) -> Option<SystemTime> {
+    use std::os::unix::fs::MetadataExt;
+
+    if metadata.nlink() > 1 {
+        return None;
+    }
+    metadata.accessed().or_else(|_| metadata.modified()).ok()
+}
+
+/// Hard links can't be told apart from copies here, so no runner's age is known from its file.
+#[cfg(not(unix))]
+fn file_last_used(_metadata: &fs::Metadata) -> Option<SystemTime> {
+    None
+}
+
+fn is_manifest_file(name: &str) -> bool {
+    name == manifest::MANIFEST_FILE_NAME || name == format!(".{}.tmp", manifest::MANIFEST_FILE_NAME)
+}
+
+fn collect_executables(
+    dir: &Path,
+    relative_dir: &mut Vec<String>,
+    runners: &RunnerManager,
+    executables: &mut Vec<InstalledExecutable>,
+) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let name = entry.file_name().to_string_lossy().to_string();
+        if relative_dir.is_empty() && is_manifest_file(&name) {
+            continue;
+        }
+        if install::is_upgrade_temp_file(&name) {
+            continue;
+        }
+
+        if file_type.is_dir() && !is_app_bundle(&name) {
+            relative_dir.push(name);
+            collect_executables(&entry.path(), relative_dir, runners, executables);
+            relative_dir.pop();
+        } else {
+            executables.push(installed_executable(
+                &entry.path(),
+                relative_dir,
+                &name,
+                runners,
+            ));
+        }
+    }
+}
+
+/// Removes `dir` and its parents while they are empty, stopping at the games root.
+fn remove_empty_parents(games_root: &Path, dir: Option<&Path>) {
+    let mut dir = dir;
+    while let Some(current) = dir {
+        if current == games_root || !current.starts_with(games_root) {
+            break;
+        }
+        if fs::remove_dir(current).is_err() {
+            break;
+        }
+        dir = current.parent();
+    }
+}
+
+/// Removes every empty folder below `dir` and returns them, `dir` itself is kept.
+fn remove_empty_dirs(dir: &Path, removed: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+        if !is_dir {
+            continue;
+        }
+
+        let path = entry.path();
+        remove_empty_dirs(&path, removed);
+        if fs::remove_dir(&path).is_ok() {
+            removed.push(path);
+        }
+    }
+}
+
+/// Whether an installed runner differs from the template, going by the hash in the manifest
+/// when there is one.
+fn is_outdated(executable: &InstalledExecutable, template_hash: &str) -> bool {
+    match &executable.template_hash {
+        Some(hash) => hash != template_hash,
+        None => {
+            manifest::file_sha256(&executable.launch_path).is_ok_and(|hash| hash != template_hash)
+        }
+    }
+}
+
+/// When the game was last played, from its manifest's launch history. Games without launches
+/// fall back to when they were installed, and installs without a manifest to the runners'
+/// access times. `None` when any runner's age is unknown, those games never expire.
+fn last_used(game: &InstalledGame) -> Option<SystemTime> {
+    let from_manifest = game
+        .manifest
+        .as_ref()
+        .map(|manifest| manifest.last_launched().unwrap_or(manifest.created))
+        .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+
+    from_manifest.or_else(|| {
+        game.executables
+            .iter()
+            .map(|executable| executable.last_used)
+            .collect::<Option<Vec<SystemTime>>>()?
+            .into_iter()
+            .max()
+    })
+}
+
+/// Installs runners into the games root and keeps track of them in each game's manifest.
+pub struct GameInstaller {
+    config: InstallerConfig,
+}
+
+impl GameInstaller {
+    pub fn new(config: InstallerConfig) -> GameInstaller {
+        GameInstaller { config }
+    }
+
+    pub fn games_root(&self) -> &Path {
+        &self.config.games_root
+    }
+
+    /// Hashes the runner template, which fails when it is missing.
+    pub fn template(&self) -> Result<RunnerTemplate, String> {
+        let hash = manifest::file_sha256(&self.config.template_path)?;
+
+        Ok(RunnerTemplate {
+            path: self.config.template_path.clone(),
+            version: self.config.template_version.clone(),
+            hash,
+        })
+    }
+
+    /// Resolves the folder an executable is installed into, rejecting `path`s and
+    /// `executable_name`s that would land outside of `<games_root>/<app_id>`.
+    pub fn game_folder_path(
+        &self,
+        path: &str,
+        executable_name: &str,
+        app_id: i64,
+    ) -> Result<PathBuf, paths::PathError> {
+        let relative_path = paths::relative_game_path(path)?;
+        paths::executable_file_name(executable_name)?;
+
+        Ok(self.game_dir(app_id).join(relative_path))
+    }
+
+    /// The game's `<games_root>/<app_id>` folder, where its manifest is.
+    pub fn game_dir(&self, app_id: i64) -> PathBuf {
+        self.config.games_root.join(app_id.to_string())
+    }
+
+    /// Copies the runner template into the game folder under the detectable executable's name,
+    /// or links it when the install strategy allows. A runner that already matches the template
+    /// is kept, and one that is running is never overwritten.
+    fn copy_runner_template(
+        &self,
+        runners: &RunnerManager,
+        template: &RunnerTemplate,
+        path: &str,
+        executable_name: &str,
+        app_id: i64,
+        display_name: Option<String>,
+    ) -> Result<InstalledRunner, String> {
+        let game_folder_path = self
+            .game_folder_path(path, executable_name, app_id)
+            .map_err(|e| format!("Invalid game path: {}", e))?;
+
+        println!("Game folder path: {:?}", game_folder_path);
+        println!(
+            "Game full path: {:?}",
+            game_folder_path.join(executable_name)
+        );
+
+        match fs::create_dir_all(&game_folder_path) {
+            Ok(_) => {
+                println!("Successfully created directory: {:?}", game_folder_path);
+            }
+            Err(e) => return Err(format!("Failed to create game folder: {}", e)),
+        };
+
+        let launch_path = launch_executable_path(&game_folder_path, executable_name);
+        if manifest::file_sha256(&launch_path).is_ok_and(|hash| hash == template.hash) {
+            return Ok(InstalledRunner {
+                message: format!("Dummy executable already at: {:?}", launch_path),
+                path: launch_path,
+                strategy: None,
+                kept: true,
+            });
+        }
+        if launch_path.exists() && runners.is_running_at(self, app_id, &launch_path) {
+            return Err(format!(
+                "The runner at {:?} is running, stop it before reinstalling",
+                launch_path
+            ));
+        }
+
+        let strategy = self.config.install_strategy;
+        println!("Creating dummy game executable from: {:?}", template.path);
+
+        if is_app_bundle(executable_name) {
+            let app_bundle_path = game_folder_path.join(executable_name);
+            let bundle_display_name = display_name
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| bundle_binary_name(executable_name));
+            let (target_executable_path, method) = make_macos_app_bundle(
+                &app_bundle_path,
+                executable_name,
+                &bundle_display_name,
+                &template.path,
+                strategy,
+            )?;
+            return Ok(InstalledRunner {
+                message: format!("Dummy app bundle created at: {:?}", target_executable_path),
+                path: target_executable_path,
+                strategy: Some(method),
+                kept: false,
+            });
+        }
+
+        let target_executable_path = game_folder_path.join(executable_name);
+        let method = install::place_runner(&template.path, &target_executable_path, strategy)?;
+
+        Ok(InstalledRunner {
+            message: format!("Dummy executable placed at: {:?}", target_executable_path),
+            path: target_executable_path,
+            strategy: Some(method),
+            kept: false,
+        })
+    }
+
+    /// Installs a runner for a game and records it in the game's manifest.
+    ///
+    /// `executable` is the detectable list's entry for it, when known. Fails rather than replace
+    /// a runner that `runners` or the manifest records as running.
+    pub fn install(
+        &self,
+        runners: &RunnerManager,
+        path: &str,
+        executable_name: &str,
+        app_id: i64,
+        display_name: Option<String>,
+        executable: Option<&DetectableExecutable>,
+    ) -> Result<InstalledRunner, String> {
+        let template = self.template()?;
+        let installed = self.copy_runner_template(
+            runners,
+            &template,
+            path,
+            executable_name,
+            app_id,
+            display_name.clone(),
+        )?;
+
+        let entry = manifest::ManifestExecutable {
+            name: detectable_name(path, executable_name),
+            os: executable
+                .map(|executable| executable.os.clone())
+                .unwrap_or_else(|| current_os().to_string()),
+            is_launcher: executable.is_some_and(|executable| executable.is_launcher),
+            template_version: template.version,
+            template_hash: template.hash,
+            installed: manifest::now_secs(),
+        };
+        let recorded = manifest::record_install(
+            &self.game_dir(app_id),
+            app_id,
+            display_name.as_deref().unwrap_or_default(),
+            entry,
+        );
+        if let Err(e) = recorded {
+            eprintln!("Failed to record install in manifest: {}", e);
+        }
+
+        Ok(installed)
+    }
+
+    /// Lists what is installed under the games root, optionally only for `app_id`. Executables
+    /// with a runner in `runners`, or one their manifest records and `/proc` confirms, are marked
+    /// as running.
+    pub fn installed(&self, runners: &RunnerManager, app_id: Option<i64>) -> Vec<InstalledGame> {
+        let Ok(entries) = fs::read_dir(&self.config.games_root) else {
+            return Vec::new();
+        };
+
+        let mut games: Vec<(i64, PathBuf)> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| {
+                let game_app_id = entry.file_name().to_str()?.parse::<i64>().ok()?;
+                Some((game_app_id, entry.path()))
+            })
+            .filter(|(game_app_id, _)| app_id.is_none() || app_id == Some(*game_app_id))
+            .collect();
+        games.sort_by_key(|(game_app_id, _)| *game_app_id);
+
+        games
+            .into_iter()
+            .map(|(app_id, path)| {
+                let mut executables = Vec::new();
+                collect_executables(&path, &mut Vec::new(), runners, &mut executables);
+
+                let manifest = manifest::read(&path);
+                if let Some(manifest) = &manifest {
+                    // Runners another process started, which `runners` doesn't know about.
+                    let recorded = recorded_launches(self, app_id, manifest);
+                    for executable in &mut executables {
+                        executable.apply_manifest(manifest);
+                        if executable.running {
+                            continue;
+                        }
+                        let launch_path = fs::canonicalize(&executable.launch_path)
+                            .unwrap_or_else(|_| executable.launch_path.clone());
+                        if let Some((runner, _)) = recorded
+                            .iter()
+                            .find(|(runner, _)| runner.executable_path == launch_path)
+                        {
+                            executable.running = true;
+                            executable.pid = Some(runner.pid);
+                        }
+                    }
+                }
+
+                InstalledGame {
+                    app_id,
+                    path,
+                    executables,
+                    manifest,
+                }
+            })
+            .collect()
+    }
+
+    /// Removes a file or folder under the games root after stopping the runners started from
+    /// it. Anything that resolves outside of the root, following symlinks, is refused.
+    fn remove_inside_root(
+        &self,
+        runners: &RunnerManager,
+        target: &Path,
+    ) -> Result<PathBuf, String> {
+        let games_root = fs::canonicalize(&self.config.games_root).map_err(|e| {
+            format!(
+                "Failed to resolve games root {:?}: {}",
+                self.config.games_root, e
+            )
+        })?;
+        let target = fs::canonicalize(target)
+            .map_err(|e| format!("Failed to resolve {:?}: {}", target, e))?;
+        if !target.starts_with(&games_root) || target == games_root {
+            return Err(format!(
+                "Refusing to delete {:?}, it is outside of the games root {:?}",
+                target, games_root
+            ));
+        }
+
+        runners.stop_in(&target)?;
+        self.stop_recorded_in(runners, &games_root, &target)?;
+
+        let removed = if target.is_dir() {
+            fs::remove_dir_all(&target)
+        } else {
+            fs::remove_file(&target)
+        };
+        removed.map_err(|e| format!("Failed to delete {:?}: {}", target, e))?;
+
+        println!("Deleted {:?}", target);
+        Ok(target)
+    }
+
+    /// Stops the runners under `target` that its game's manifest records, which another process
+    /// may have started. Fails when any of them can't be stopped, so nothing is deleted under a
+    /// running runner.
+    fn stop_recorded_in(
+        &self,
+        runners: &RunnerManager,
+        games_root: &Path,
+        target: &Path,
+    ) -> Result<(), String> {
+        let app_id = target
+            .strip_prefix(games_root)
+            .ok()
+            .and_then(|relative| relative.components().next())
+            .and_then(|component| component.as_os_str().to_str()?.parse::<i64>().ok());
+        let Some(app_id) = app_id else {
+            return Ok(());
+        };
+        let Some(manifest) = manifest::read(&self.game_dir(app_id)) else {
+            return Ok(());
+        };
+
+        let recorded: Vec<RunnerProcess> = recorded_launches(self, app_id, &manifest)
+            .into_iter()
+            .map(|(runner, _)| runner)
+            .filter(|runner| runner.executable_path.starts_with(target))
+            .collect();
+        if recorded.is_empty() {
+            return Ok(());
+        }
+
+        let report = runners.stop_recorded(recorded);
+        if !report.errors.is_empty() {
+            return Err(format!(
+                "Refusing to delete {:?}, its runners could not be stopped: {}",
+                target,
+                report.errors.join("; ")
+            ));
+        }
+        Ok(())
+    }
+
+    /// Removes an installed game, or only one of its executables when `executable` is given as
+    /// the detectable list names it, for example `bin/game.exe`. Runners started from it are
+    /// stopped first. Returns what was deleted.
+    pub fn uninstall(
+        &self,
+        runners: &RunnerManager,
+        app_id: i64,
+        executable: Option<&str>,
+    ) -> Result<PathBuf, String> {
+        let game_dir = self.game_dir(app_id);
+
+        let (target, manifest_name) = match executable {
+            Some(executable) => {
+                let (path, executable_name) = executable
+                    .rsplit_once(['/', '\\'])
+                    .unwrap_or(("", executable));
+                let target = self
+                    .game_folder_path(path, executable_name, app_id)
+                    .map_err(|e| format!("Invalid game path: {}", e))?
+                    .join(executable_name);
+                (target, Some(detectable_name(path, executable_name)))
+            }
+            None => (game_dir.clone(), None),
+        };
+        if !target.exists() {
+            return Err(format!("{:?} is not installed", target));
+        }
+
+        let removed = self.remove_inside_root(runners, &target)?;
+        if let Some(manifest_name) = manifest_name {
+            manifest::record_uninstall(&game_dir, &manifest_name)?;
+        }
+        remove_empty_parents(&self.config.games_root, target.parent());
+
+        Ok(removed)
+    }
+
+    /// Cleans up the games root: deletes games whose runners were not launched within
+    /// `max_age`, runner copies that differ from the template, and empty folders. Only runners
+    /// listed in a manifest are removed as outdated, and running ones are skipped.
+    pub fn gc(
+        &self,
+        runners: &RunnerManager,
+        max_age: Option<Duration>,
+    ) -> Result<GcReport, String> {
+        let mut report = GcReport::default();
+        if !self.config.games_root.is_dir() {
+            return Ok(report);
+        }
+        let template_hash = self.template()?.hash;
+
+        let now = SystemTime::now();
+        for game in self.installed(runners, None) {
+            let expired = match (max_age, last_used(&game)) {
+                (Some(max_age), Some(last_used)) => {
+                    now.duration_since(last_used).is_ok_and(|age| age > max_age)
+                }
+                _ => false,
+            };
+            let running: Vec<&InstalledExecutable> = game
+                .executables
+                .iter()
+                .filter(|executable| executable.running)
+                .collect();
+            if expired && running.is_empty() {
+                self.remove_inside_root(runners, &game.path)?;
+                report.removed_games.push(game.app_id);
+                continue;
+            }
+            if expired {
+                println!("Not removing game {}, it is running", game.app_id);
+                report.skipped.extend(
+                    running
+                        .iter()
+                        .map(|executable| executable.full_path.clone()),
+                );
+                continue;
+            }
+
+            for executable in game.executables {
+                // Files the manifest doesn't list may not be runners at all.
+                if executable.template_hash.is_none() || !is_outdated(&executable, &template_hash) {
+                    continue;
+                }
+                if executable.running {
+                    println!("Not removing {:?}, it is running", executable.full_path);
+                    report.skipped.push(executable.full_path);
+                    continue;
+                }
+                self.remove_inside_root(runners, &executable.full_path)?;
+                manifest::record_uninstall(&game.path, &executable.name)?;
+                report.removed_executables.push(executable.full_path);
+            }
+        }
+
+        remove_empty_dirs(&self.config.games_root, &mut report.removed_dirs);
+        Ok(report)
+    }
+
+    /// Replaces every installed runner made from an older template with a fresh copy of it.
+    /// Runners that are running, in `runners` or going by the manifests, are skipped, they get
+    /// upgraded the next time this runs.
+    pub fn upgrade(&self, runners: &RunnerManager) -> Result<UpgradeReport, String> {
+        let template = self.template()?;
+        let mut report = UpgradeReport {
+            template_version: template.version.clone(),
+            ..Default::default()
+        };
+
+        for game in self.installed(runners, None) {
+            for executable in game.executables {
+                if !is_outdated(&executable, &template.hash) {
+                    continue;
+                }
+                if executable.running {
+                    println!("Not upgrading {:?}, it is running", executable.full_path);
+                    report.skipped.push(executable.full_path);
+                    continue;
+                }
+
+                let target = &executable.launch_path;
+                let upgraded =
+                    install::replace_runner(&template.path, target, self.config.install_strategy)
+                        .and_then(|_| {
+                            manifest::record_upgrade(
+                                &game.path,
+                                &executable.name,
+                                &template.version,
+                                &template.hash,
+                            )
+                        });
+                match upgraded {
+                    Ok(()) => {
+                        println!(
+                            "Upgraded {:?} to runner {}",
+                            executable.full_path, template.version
+                        );
+                        report.upgraded.push(executable.full_path);
+                    }
+                    Err(error) => {
+                        eprintln!("Failed to upgrade {:?}: {}", executable.full_path, error);
+                        report.failed.push(FailedUpgrade {
+                            path: executable.full_path,
+                            error,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
@@ -0,0 +1,23 @@
+//! The backend without Tauri: installing runners into a games root, launching and stopping
+//! them, and showing presence through Discord. Nothing in here reads settings or app
+//! directories, everything it needs is passed in, so the app, the CLI and the tests each set
+//! it up their own way.
+
+mod installer;
+mod presence;
+mod runners;
+
+pub use installer::{
+    current_os, FailedUpgrade, GameInstaller, GcReport, InstalledExecutable, InstalledGame,
+    InstalledRunner, InstallerConfig, RunnerTemplate, UpgradeReport,
+};
+pub use presence::{parse_app_id, ClientSession, ClientStatus, ConnectionState, PresenceManager};
+pub use runners::{
+    is_recorded_runner, RunnerExit, RunnerManager, RunnerProcess, SpawnedRunner, StoppedRunners,
+    RUNNER_EXIT_TIMEOUT,
+};
+
+pub use crate::detectable::DetectableExecutable;
+pub use crate::install::{InstallMethod, InstallStrategy};
+pub use crate::manifest::{GameManifest, LaunchRecord, ManifestExecutable};
+pub use crate::rpc::{ConnectionEvent, Error as PresenceError, DEFAULT_CONNECT_TIMEOUT};
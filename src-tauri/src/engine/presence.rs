@@ -0,0 +1,240 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::rpc;
+use crate::runner;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connected,
+    /// The client lost its connection and is waiting for Discord to come back.
+    Reconnecting,
+    Disconnected,
+}
+
+/// The connection state, user and current activity of the client for an app.
+#[derive(Serialize)]
+pub struct ClientStatus {
+    pub app_id: String,
+    pub state: ConnectionState,
+    /// Missing when there is no client for the app.
+    #[serde(flatten)]
+    pub session: Option<ClientSession>,
+}
+
+#[derive(Serialize)]
+pub struct ClientSession {
+    pub user_id: String,
+    pub username: String,
+    pub activity: Option<rpc::ds::activity::Activity>,
+}
+
+fn client_status(client: &rpc::Client) -> ClientStatus {
+    ClientStatus {
+        app_id: client.app_id.to_string(),
+        state: if client.is_connected() {
+            ConnectionState::Connected
+        } else {
+            ConnectionState::Reconnecting
+        },
+        session: Some(ClientSession {
+            user_id: client.user.id.to_string(),
+            username: client.user.username.clone(),
+            activity: client.last_activity(),
+        }),
+    }
+}
+
+pub fn parse_app_id(app_id: &str) -> Result<i64, rpc::Error> {
+    runner::to_app_id(app_id).map(|app_id| app_id as i64)
+}
+
+/// Keeps a Discord client per app_id, so several apps can show presence at once.
+///
+/// Clients stay in the pool while a request on them is in flight, so their status and
+/// disconnects aren't lost meanwhile. Whoever drops the last handle to a client that left the
+/// pool disconnects it.
+#[derive(Default)]
+pub struct PresenceManager {
+    clients: Mutex<HashMap<i64, Arc<rpc::Client>>>,
+}
+
+impl PresenceManager {
+    pub fn new() -> PresenceManager {
+        PresenceManager::default()
+    }
+
+    fn get(&self, app_id: i64) -> Option<Arc<rpc::Client>> {
+        let clients = self.clients.lock().unwrap();
+        clients.get(&app_id).cloned()
+    }
+
+    fn take(&self, app_id: i64) -> Option<Arc<rpc::Client>> {
+        let mut clients = self.clients.lock().unwrap();
+        clients.remove(&app_id)
+    }
+
+    /// Drops a handle to a client, disconnecting it if it was the last one. A client still in
+    /// the pool is never disconnected here, and one that left it is disconnected by whoever
+    /// releases it last.
+    async fn release(client: Arc<rpc::Client>) -> Result<(), rpc::Error> {
+        match Arc::into_inner(client) {
+            Some(client) => client.disconnect().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Adds a client to the pool, disconnecting the client it replaces for the same app_id.
+    /// The new client is kept even when the old one fails to disconnect.
+    async fn store(&self, client: rpc::Client) {
+        let replaced = {
+            let mut clients = self.clients.lock().unwrap();
+            clients.insert(client.app_id, Arc::new(client))
+        };
+
+        if let Some(replaced) = replaced {
+            println!("Replacing Discord client for app_id {}", replaced.app_id);
+            if let Err(e) = PresenceManager::release(replaced).await {
+                eprintln!("{}", e);
+            }
+        }
+    }
+
+    pub fn is_connected(&self, app_id: i64) -> bool {
+        let clients = self.clients.lock().unwrap();
+        clients
+            .get(&app_id)
+            .is_some_and(|client| client.is_connected())
+    }
+
+    /// Connects a Discord client for the app in `activity_json`, sets its activity and adds it
+    /// to the pool. When a client is already connected for the app only its presence changes,
+    /// and `connect_timeout` and `on_connection` go unused.
+    ///
+    /// `on_connection` is told when a new client loses its connection and gets it back.
+    pub async fn connect(
+        &self,
+        activity_json: String,
+        connect_timeout: Duration,
+        on_connection: impl Fn(rpc::ConnectionEvent) + Send + Sync + 'static,
+    ) -> Result<i64, rpc::Error> {
+        let activity = runner::parse_activity_json(&activity_json)?;
+        let app_id = parse_app_id(&activity.app_id)?;
+
+        if self.is_connected(app_id) {
+            let activity_result = runner::create_activity(activity_json)?;
+            self.update_pooled_activity(app_id, activity_result.activity)
+                .await?;
+            return Ok(app_id);
+        }
+
+        let client = runner::set_activity(activity_json, connect_timeout)
+            .await
+            .inspect_err(|e| println!("Failed to set activity: {}", e))?;
+
+        client.watch_connection(on_connection);
+        self.store(client).await;
+
+        Ok(app_id)
+    }
+
+    /// Changes the presence on the pooled client for `app_id` without a new IPC handshake.
+    async fn update_pooled_activity(
+        &self,
+        app_id: i64,
+        activity: rpc::ds::activity::ActivityBuilder,
+    ) -> Result<(), rpc::Error> {
+        let client = self.get(app_id).ok_or(rpc::Error::NotConnected(app_id))?;
+        let updated = client.update_activity(activity).await;
+        let released = PresenceManager::release(client).await;
+
+        updated.and(released)
+    }
+
+    /// Sets the activity in `activity_json` on the client already connected for its app_id,
+    /// reusing the connection instead of reconnecting.
+    pub async fn update_activity(&self, activity_json: String) -> Result<(), rpc::Error> {
+        let activity_result = runner::create_activity(activity_json)?;
+
+        self.update_pooled_activity(activity_result.app_id as i64, activity_result.activity)
+            .await
+    }
+
+    /// Blanks the presence of the client connected for `app_id` while keeping it connected.
+    pub async fn clear_activity(&self, app_id: i64) -> Result<(), rpc::Error> {
+        let client = self.get(app_id).ok_or(rpc::Error::NotConnected(app_id))?;
+        let cleared = client.clear_activity().await;
+        let released = PresenceManager::release(client).await;
+
+        cleared.and(released)
+    }
+
+    /// Clears the activity of the client connected for `app_id` and disconnects it.
+    pub async fn end_session(&self, app_id: i64) -> Result<(), rpc::Error> {
+        if let Some(client) = self.take(app_id) {
+            if let Err(e) = client.clear_activity().await {
+                eprintln!("{}", e);
+            }
+            PresenceManager::release(client).await?;
+            println!("Cleared Discord activity for app_id {}", app_id);
+        }
+        Ok(())
+    }
+
+    /// Disconnects the client for `app_id`, returns whether there was one. A request still in
+    /// flight on it finishes first.
+    pub async fn disconnect(&self, app_id: i64) -> Result<bool, rpc::Error> {
+        match self.take(app_id) {
+            Some(client) => {
+                PresenceManager::release(client).await?;
+                println!("Disconnected Discord client for app_id {}", app_id);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Disconnects every client, returning the first error after trying all of them.
+    pub async fn disconnect_all(&self) -> Result<(), rpc::Error> {
+        let clients: Vec<Arc<rpc::Client>> = {
+            let mut clients = self.clients.lock().unwrap();
+            clients.drain().map(|(_, client)| client).collect()
+        };
+
+        let mut result = Ok(());
+        for client in clients {
+            let app_id = client.app_id;
+            match PresenceManager::release(client).await {
+                Ok(()) => println!("Disconnected Discord client for app_id {}", app_id),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    result = result.and(Err(e));
+                }
+            }
+        }
+        result
+    }
+
+    pub fn status(&self, app_id: i64) -> ClientStatus {
+        let clients = self.clients.lock().unwrap();
+        match clients.get(&app_id) {
+            Some(client) => client_status(client),
+            None => ClientStatus {
+                app_id: app_id.to_string(),
+                state: ConnectionState::Disconnected,
+                session: None,
+            },
+        }
+    }
+
+    pub fn list(&self) -> Vec<ClientStatus> {
+        let clients = self.clients.lock().unwrap();
+        clients
+            .values()
+            .map(|client| client_status(client))
+            .collect()
+    }
+}
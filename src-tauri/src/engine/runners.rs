@@ -0,0 +1,661 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ExitStatus};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::installer::{detectable_name, is_app_bundle, launch_executable_path};
+use super::GameInstaller;
+use crate::manifest;
+
+// How long to wait for stopped runners to exit before touching their files.
+pub const RUNNER_EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A runner process started by a `RunnerManager`.
+#[derive(Clone)]
+pub struct RunnerProcess {
+    pub pid: u32,
+    /// The runner is spawned as the leader of its own process group, so this is its PID on Unix.
+    pub process_group: Option<u32>,
+    pub app_id: i64,
+    pub executable_name: String,
+    pub executable_path: PathBuf,
+    /// The game's `<games_root>/<app_id>` folder, where its manifest is.
+    pub game_dir: PathBuf,
+    /// Set when the runner is stopped, so an exit can be told apart from a crash.
+    pub stop_requested: bool,
+}
+
+/// How a runner ended.
+pub struct RunnerExit {
+    pub runner: RunnerProcess,
+    pub code: Option<i32>,
+    pub signal: Option<i32>,
+    /// Set when waiting on the runner failed, its exit is unknown then.
+    pub error: Option<String>,
+}
+
+impl RunnerExit {
+    fn new(runner: RunnerProcess, status: std::io::Result<ExitStatus>) -> RunnerExit {
+        let (code, signal, error) = match &status {
+            Ok(status) => (status.code(), exit_signal(status), None),
+            Err(e) => (None, None, Some(e.to_string())),
+        };
+        RunnerExit {
+            runner,
+            code,
+            signal,
+            error,
+        }
+    }
+
+    /// Whether the runner died on its own with a failure code or a signal. Closing the runner
+    /// from its own window exits cleanly and is not a crash.
+    pub fn crashed(&self) -> bool {
+        !self.runner.stop_requested && (self.error.is_some() || self.code != Some(0))
+    }
+}
+
+/// A runner started by `RunnerManager::spawn`, which the caller waits on.
+pub struct SpawnedRunner {
+    pub runner: RunnerProcess,
+    child: Child,
+}
+
+impl SpawnedRunner {
+    /// Waits for `duration` and stops the runner, unless it exits first, then records its exit.
+    /// The runner's `stop_requested` tells whether it was stopped or exited on its own.
+    pub fn run_for(mut self, duration: Duration) -> Result<RunnerExit, String> {
+        let deadline = Instant::now() + duration;
+        loop {
+            if self
+                .child
+                .try_wait()
+                .map_err(|e| format!("Failed to check on the runner: {}", e))?
+                .is_some()
+            {
+                break;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                self.runner.stop_requested = true;
+                stop_runner(&self.runner)?;
+                break;
+            }
+            std::thread::sleep((deadline - now).min(Duration::from_secs(1)));
+        }
+
+        let exit = RunnerExit::new(self.runner, self.child.wait());
+        if let Some(error) = &exit.error {
+            return Err(format!("Failed to wait for the runner: {}", error));
+        }
+        manifest::record_exit(&exit.runner.game_dir, exit.runner.pid, exit.code)
+            .unwrap_or_else(|e| eprintln!("Failed to record exit in manifest: {}", e));
+        Ok(exit)
+    }
+}
+
+/// What `RunnerManager::stop_recorded` stopped.
+#[derive(Default)]
+pub struct StoppedRunners {
+    pub stopped: Vec<RunnerProcess>,
+    pub errors: Vec<String>,
+}
+
+#[cfg(unix)]
+fn exit_signal(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+/// Reads the process group of `pid` from `/proc/<pid>/stat`.
+#[cfg(target_os = "linux")]
+fn proc_process_group(pid: u32) -> Option<u32> {
+    let stat = fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name is wrapped in parentheses and may contain spaces, so skip past it first.
+    let fields = &stat[stat.rfind(')')? + 1..];
+    fields.split_whitespace().nth(2)?.parse().ok()
+}
+
+/// Confirms through `/proc` that `pid` is still the runner we spawned before signalling it.
+/// `Ok(false)` means it has exited, an error that the PID now runs something else, like a
+/// runner whose file was deleted or replaced.
+#[cfg(target_os = "linux")]
+fn check_runner(runner: &RunnerProcess) -> Result<bool, String> {
+    let Ok(exe) = fs::read_link(format!("/proc/{}/exe", runner.pid)) else {
+        return Ok(false);
+    };
+
+    if exe != runner.executable_path {
+        return Err(format!(
+            "Refusing to signal pid {}: running {:?} instead of {:?}",
+            runner.pid, exe, runner.executable_path
+        ));
+    }
+
+    match runner.process_group {
+        Some(pgid) if proc_process_group(runner.pid) != Some(pgid) => Err(format!(
+            "Refusing to signal pid {}: no longer in process group {}",
+            runner.pid, pgid
+        )),
+        _ => Ok(true),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn check_runner(_runner: &RunnerProcess) -> Result<bool, String> {
+    // The supervisor removes the entry right after reaping the child, so its PID is still ours.
+    Ok(true)
+}
+
+/// Confirms a runner from a manifest's launch records is still running. Those may have been
+/// started by another process, which only `/proc` can confirm.
+#[cfg(target_os = "linux")]
+pub fn is_recorded_runner(runner: &RunnerProcess) -> bool {
+    check_runner(runner).unwrap_or(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn is_recorded_runner(_runner: &RunnerProcess) -> bool {
+    false
+}
+
+#[cfg(unix)]
+fn signal_runner(runner: &RunnerProcess) -> Result<(), String> {
+    // A negative PID signals the whole process group.
+    let target = match runner.process_group {
+        Some(pgid) => -(pgid as libc::pid_t),
+        None => runner.pid as libc::pid_t,
+    };
+
+    // SAFETY: `kill` only sends a signal, and `check_runner` confirmed the target is ours.
+    if unsafe { libc::kill(target, libc::SIGTERM) } == 0 {
+        return Ok(());
+    }
+    let error = std::io::Error::last_os_error();
+    if error.raw_os_error() == Some(libc::ESRCH) {
+        // It exited since it was checked.
+        return Ok(());
+    }
+    Err(format!("Failed to stop process {}: {}", runner.pid, error))
+}
+
+#[cfg(target_os = "windows")]
+fn signal_runner(runner: &RunnerProcess) -> Result<(), String> {
+    let output = std::process::Command::new("taskkill")
+        .arg("/F")
+        .arg("/T")
+        .arg("/PID")
+        .arg(runner.pid.to_string())
+        .output()
+        .map_err(|e| format!("Failed to execute taskkill: {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to stop process: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    }
+}
+
+fn stop_runner(runner: &RunnerProcess) -> Result<(), String> {
+    if !check_runner(runner)? {
+        return Ok(());
+    }
+
+    println!(
+        "Stopping runner pid {}: {:?}",
+        runner.pid, runner.executable_path
+    );
+    signal_runner(runner)
+}
+
+#[cfg(target_os = "macos")]
+fn launch_macos_app_bundle(app_bundle_path: &Path, title: &str) -> Result<(), String> {
+    let mut command = std::process::Command::new("open");
+    command
+        .arg("-n")
+        .arg("-g")
+        .arg("-a")
+        .arg(app_bundle_path)
+        .arg("--args")
+        .arg("--title")
+        .arg(title);
+
+    command
+        .spawn()
+        .map_err(|e| format!("Failed to launch app bundle with open: {}", e))?;
+
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+fn stop_macos_app_bundle(exec_name: &str) -> Result<(), String> {
+    // Bundles are started through `open`, so there is no child PID of ours to track.
+    let bundle_pattern = format!(
+        "{}/Contents/MacOS/{}",
+        exec_name,
+        super::installer::bundle_binary_name(exec_name)
+    );
+    let output = std::process::Command::new("pkill")
+        .arg("-f")
+        .arg(&bundle_pattern)
+        .output()
+        .map_err(|e| format!("Failed to execute pkill: {}", e))?;
+
+    if output.status.success() || output.status.code() == Some(1) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to stop process: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    }
+}
+
+/// The runners a game's manifest records as launched and not ended that `/proc` confirms are
+/// still running, with when they were started.
+pub(crate) fn recorded_launches(
+    installer: &GameInstaller,
+    app_id: i64,
+    manifest: &manifest::GameManifest,
+) -> Vec<(RunnerProcess, u64)> {
+    let mut runners = Vec::new();
+    for launch in manifest
+        .launches
+        .iter()
+        .filter(|launch| launch.ended.is_none())
+    {
+        let Some(pid) = launch.pid else {
+            continue;
+        };
+        let (path, executable_name) = launch
+            .executable
+            .rsplit_once('/')
+            .unwrap_or(("", launch.executable.as_str()));
+        let Ok(folder) = installer.game_folder_path(path, executable_name, app_id) else {
+            continue;
+        };
+        let executable_path = launch_executable_path(&folder, executable_name);
+
+        let runner = RunnerProcess {
+            pid,
+            process_group: if cfg!(unix) { Some(pid) } else { None },
+            app_id,
+            executable_name: executable_name.to_string(),
+            executable_path: fs::canonicalize(&executable_path).unwrap_or(executable_path),
+            game_dir: installer.game_dir(app_id),
+            stop_requested: false,
+        };
+        if is_recorded_runner(&runner) {
+            runners.push((runner, launch.started));
+        }
+    }
+    runners
+}
+
+/// Starts installed runners and keeps track of the ones it launched. Stopping a game only
+/// ever targets runners in its registry, or runners confirmed through `/proc`.
+#[derive(Default)]
+pub struct RunnerManager {
+    // Every runner launched, keyed by PID. Entries are removed by the runner's supervisor once
+    // the child has been reaped.
+    registry: Arc<Mutex<HashMap<u32, RunnerProcess>>>,
+}
+
+impl RunnerManager {
+    pub fn new() -> RunnerManager {
+        RunnerManager::default()
+    }
+
+    /// Starts an installed runner and records the launch in the game's manifest. The runner is
+    /// not registered, the caller waits on it.
+    ///
+    /// Returns `None` for macOS app bundles, which are launched through `open` and aren't tracked.
+    pub fn spawn(
+        &self,
+        installer: &GameInstaller,
+        name: &str,
+        path: &str,
+        executable_name: &str,
+        app_id: i64,
+    ) -> Result<Option<SpawnedRunner>, String> {
+        let game_folder_path = installer
+            .game_folder_path(path, executable_name, app_id)
+            .map_err(|e| format!("Invalid game path: {}", e))?;
+        let game_dir = installer.game_dir(app_id);
+
+        if is_app_bundle(executable_name) {
+            #[cfg(target_os = "macos")]
+            {
+                let bundle_path = game_folder_path.join(executable_name);
+                launch_macos_app_bundle(&bundle_path, name)?;
+                manifest::record_launch(&game_dir, &detectable_name(path, executable_name), None)
+                    .unwrap_or_else(|e| eprintln!("Failed to record launch in manifest: {}", e));
+                return Ok(None);
+            }
+
+            #[cfg(not(target_os = "macos"))]
+            {
+                let _ = name;
+                return Err("App bundle launches are only supported on macOS".to_string());
+            }
+        }
+
+        let executable_path = launch_executable_path(&game_folder_path, executable_name);
+        let executable_path = fs::canonicalize(&executable_path).unwrap_or(executable_path);
+
+        let mut cmd = std::process::Command::new(&executable_path);
+        cmd.args(["--title", name]).current_dir(game_folder_path);
+
+        // Platform-specific process spawning
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            cmd.process_group(0); // Create new process group on Unix
+        }
+
+        match cmd.spawn() {
+            Ok(child) => {
+                let pid = child.id();
+                let runner = RunnerProcess {
+                    pid,
+                    process_group: if cfg!(unix) { Some(pid) } else { None },
+                    app_id,
+                    executable_name: executable_name.to_string(),
+                    executable_path,
+                    game_dir,
+                    stop_requested: false,
+                };
+
+                manifest::record_launch(
+                    &runner.game_dir,
+                    &detectable_name(path, executable_name),
+                    Some(pid),
+                )
+                .unwrap_or_else(|e| eprintln!("Failed to record launch in manifest: {}", e));
+
+                Ok(Some(SpawnedRunner { runner, child }))
+            }
+            Err(e) => Err(format!("Failed to start process: {}", e)),
+        }
+    }
+
+    /// Starts an installed runner, registers it and supervises it on a thread of its own.
+    /// Once it has exited, its exit is recorded in the manifest and passed to `on_exit`.
+    ///
+    /// Returns the runner, or `None` for macOS app bundles which are launched through `open`.
+    pub fn launch(
+        &self,
+        installer: &GameInstaller,
+        name: &str,
+        path: &str,
+        executable_name: &str,
+        app_id: i64,
+        on_exit: impl FnOnce(RunnerExit) + Send + 'static,
+    ) -> Result<Option<RunnerProcess>, String> {
+        let Some(SpawnedRunner { runner, mut child }) =
+            self.spawn(installer, name, path, executable_name, app_id)?
+        else {
+            return Ok(None);
+        };
+
+        println!(
+            "Registered runner pid {} (app_id {}): {:?}",
+            runner.pid, runner.app_id, runner.executable_path
+        );
+        self.registry
+            .lock()
+            .unwrap()
+            .insert(runner.pid, runner.clone());
+
+        let registry = self.registry.clone();
+        let pid = runner.pid;
+        std::thread::spawn(move || {
+            let status = child.wait();
+
+            let Some(runner) = registry.lock().unwrap().remove(&pid) else {
+                return;
+            };
+            let exit = RunnerExit::new(runner, status);
+
+            manifest::record_exit(&exit.runner.game_dir, pid, exit.code)
+                .unwrap_or_else(|e| eprintln!("Failed to record exit in manifest: {}", e));
+
+            println!(
+                "Runner pid {} exited (code: {:?}, signal: {:?}, requested: {})",
+                pid, exit.code, exit.signal, exit.runner.stop_requested
+            );
+            on_exit(exit);
+        });
+
+        Ok(Some(runner))
+    }
+
+    /// Stops the registered runners for `exec_name`, optionally narrowed down to `app_id`. When
+    /// none are registered, like after a restart, it stops the ones the manifests record instead.
+    pub fn stop(
+        &self,
+        installer: &GameInstaller,
+        exec_name: &str,
+        app_id: Option<i64>,
+    ) -> Result<(), String> {
+        #[cfg(target_os = "macos")]
+        if is_app_bundle(exec_name) {
+            return stop_macos_app_bundle(exec_name);
+        }
+
+        let runners: Vec<RunnerProcess> = {
+            let mut registry = self.registry.lock().unwrap();
+            registry
+                .values_mut()
+                .filter(|runner| runner.executable_name == exec_name)
+                .filter(|runner| app_id.is_none() || app_id == Some(runner.app_id))
+                .map(|runner| {
+                    runner.stop_requested = true;
+                    runner.clone()
+                })
+                .collect()
+        };
+        if runners.is_empty() {
+            let recorded: Vec<RunnerProcess> = self
+                .recorded(installer)
+                .into_iter()
+                .map(|(runner, _)| runner)
+                .filter(|runner| runner.executable_name == exec_name)
+                .filter(|runner| app_id.is_none() || app_id == Some(runner.app_id))
+                .collect();
+            if recorded.is_empty() {
+                // Nothing is running under this name, which is fine
+                println!("No running runner for {}", exec_name);
+                return Ok(());
+            }
+
+            let report = self.stop_recorded(recorded);
+            return if report.errors.is_empty() {
+                Ok(())
+            } else {
+                Err(report.errors.join("\n"))
+            };
+        }
+
+        let errors: Vec<String> = runners
+            .iter()
+            .filter_map(|runner| stop_runner(runner).err())
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    /// Stops a runner returned by `launch`, `pid` is `None` for macOS app bundles.
+    pub fn stop_pid(&self, pid: Option<u32>, executable_name: &str) -> Result<(), String> {
+        match pid {
+            Some(pid) => {
+                let runner = {
+                    let mut registry = self.registry.lock().unwrap();
+                    registry.get_mut(&pid).map(|runner| {
+                        runner.stop_requested = true;
+                        runner.clone()
+                    })
+                };
+                match runner {
+                    Some(runner) => stop_runner(&runner),
+                    None => Ok(()),
+                }
+            }
+            #[cfg(target_os = "macos")]
+            None => stop_macos_app_bundle(executable_name),
+            #[cfg(not(target_os = "macos"))]
+            None => {
+                let _ = executable_name;
+                Ok(())
+            }
+        }
+    }
+
+    /// Stops every registered runner launched from inside `dir` and waits for them to exit, so
+    /// their files can be removed or replaced.
+    pub fn stop_in(&self, dir: &Path) -> Result<(), String> {
+        let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let runners: Vec<RunnerProcess> = {
+            let mut registry = self.registry.lock().unwrap();
+            registry
+                .values_mut()
+                .filter(|runner| runner.executable_path.starts_with(&dir))
+                .map(|runner| {
+                    runner.stop_requested = true;
+                    runner.clone()
+                })
+                .collect()
+        };
+
+        for runner in &runners {
+            stop_runner(runner)?;
+        }
+
+        // The supervisor drops a runner from the registry once it has been reaped.
+        let deadline = Instant::now() + RUNNER_EXIT_TIMEOUT;
+        loop {
+            let still_running = {
+                let registry = self.registry.lock().unwrap();
+                runners
+                    .iter()
+                    .any(|runner| registry.contains_key(&runner.pid))
+            };
+            if !still_running {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "Timed out waiting for the runners in {:?} to exit",
+                    dir
+                ));
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    /// Whether `pid` is a registered runner that nobody asked to stop.
+    pub fn is_active(&self, pid: u32) -> bool {
+        let registry = self.registry.lock().unwrap();
+        registry
+            .get(&pid)
+            .is_some_and(|runner| !runner.stop_requested)
+    }
+
+    pub fn has_running(&self) -> bool {
+        !self.registry.lock().unwrap().is_empty()
+    }
+
+    /// Finds the PID of the registered runner launched from `executable_path`.
+    pub fn pid_for_path(&self, executable_path: &Path) -> Option<u32> {
+        let executable_path = fs::canonicalize(executable_path).ok()?;
+        let registry = self.registry.lock().unwrap();
+        registry
+            .values()
+            .find(|runner| runner.executable_path == executable_path)
+            .map(|runner| runner.pid)
+    }
+
+    /// Finds the runners still running according to the launch records in the games' manifests,
+    /// with when they were started. Unlike the registry, this includes runners started by other
+    /// processes.
+    pub fn recorded(&self, installer: &GameInstaller) -> Vec<(RunnerProcess, u64)> {
+        installer
+            .installed(self, None)
+            .iter()
+            .filter_map(|game| {
+                Some(recorded_launches(
+                    installer,
+                    game.app_id,
+                    game.manifest.as_ref()?,
+                ))
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Whether a runner started from `executable_path` is running, launched by this manager
+    /// or, going by the game's manifest, by another process.
+    pub fn is_running_at(
+        &self,
+        installer: &GameInstaller,
+        app_id: i64,
+        executable_path: &Path,
+    ) -> bool {
+        if self.pid_for_path(executable_path).is_some() {
+            return true;
+        }
+
+        let executable_path =
+            fs::canonicalize(executable_path).unwrap_or_else(|_| executable_path.to_path_buf());
+        manifest::read(&installer.game_dir(app_id)).is_some_and(|manifest| {
+            recorded_launches(installer, app_id, &manifest)
+                .iter()
+                .any(|(runner, _)| runner.executable_path == executable_path)
+        })
+    }
+
+    /// Stops runners found by `recorded` and records their exits once they are gone, since the
+    /// process that started them may not be around to.
+    pub fn stop_recorded(&self, runners: Vec<RunnerProcess>) -> StoppedRunners {
+        let mut report = StoppedRunners::default();
+        let mut signalled = Vec::new();
+        for mut runner in runners {
+            runner.stop_requested = true;
+            match stop_runner(&runner) {
+                Ok(()) => signalled.push(runner),
+                Err(e) => report.errors.push(e),
+            }
+        }
+
+        let deadline = Instant::now() + RUNNER_EXIT_TIMEOUT;
+        for runner in signalled {
+            while is_recorded_runner(&runner) && Instant::now() < deadline {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            if is_recorded_runner(&runner) {
+                report
+                    .errors
+                    .push(format!("Runner pid {} did not exit", runner.pid));
+                continue;
+            }
+            manifest::record_exit(&runner.game_dir, runner.pid, None)
+                .unwrap_or_else(|e| eprintln!("Failed to record exit in manifest: {}", e));
+            report.stopped.push(runner);
+        }
+        report
+    }
+}
@@ -0,0 +1,866 @@
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_http::reqwest::{self, header, StatusCode};
+
+use crate::app_dirs;
+use crate::detectable::{diff_game_lists, parse_game_list, DetectableGame, GameList, GameListDiff};
+use crate::manifest;
+use crate::search;
+use crate::settings;
+use crate::signature::{self, SignatureStatus, UnsignedPolicy};
+
+const GH_MIRROR_URL: &str = "https://markterence.github.io/discord-quest-completer/detectable.json";
+const DISCORD_URL: &str = "https://discord.com/api/applications/detectable";
+
+// Copied into the app's resources at build time, see the workflows.
+const BUNDLED_RESOURCE_NAME: &str = "resources/gamelist.json";
+
+const DEFAULT_TIMEOUT_SECS: u64 = 15;
+
+// Waited before each retry, multiplied by the attempt number.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+/// Somewhere a game list can be loaded from. Sources are tried in order until one has a valid
+/// list.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GameListSource {
+    Http {
+        url: String,
+        #[serde(default = "default_timeout_secs")]
+        timeout_secs: u64,
+        /// Extra attempts after the first one fails.
+        #[serde(default)]
+        retries: u32,
+        #[serde(flatten)]
+        signature: SignatureConfig,
+    },
+    /// A JSON file on disk, for example for testing.
+    File {
+        path: PathBuf,
+        #[serde(flatten)]
+        signature: SignatureConfig,
+    },
+    /// The last list fetched from an HTTP source.
+    Cache,
+    /// The list shipped with the app, as old as the build.
+    Bundled,
+}
+
+/// Whether a source publishes a detached signature next to its list, `<url>.sig` or
+/// `<path>.sig`, see `signature::check`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SignatureConfig {
+    #[serde(default)]
+    pub signed: bool,
+    /// Base64 ed25519 public key, `None` uses the one embedded at build time.
+    #[serde(default)]
+    pub public_key: Option<String>,
+}
+
+impl SignatureConfig {
+    /// Whether the signature is worth fetching, without a public key it can't be checked.
+    fn wants_signature(&self) -> bool {
+        self.signed
+            && self
+                .public_key
+                .as_deref()
+                .or(signature::EMBEDDED_PUBLIC_KEY)
+                .is_some()
+    }
+
+    /// `signature` is what fetching it gave, `Ok(None)` when there is none or it wasn't fetched.
+    /// A signature that couldn't be fetched counts as missing.
+    fn check(
+        &self,
+        body: &str,
+        signature: Result<Option<String>, String>,
+        policy: UnsignedPolicy,
+    ) -> Result<SignatureStatus, String> {
+        if !self.signed {
+            return Ok(SignatureStatus::NotChecked);
+        }
+        let signature = match signature {
+            Ok(signature) => signature,
+            Err(e) => {
+                eprintln!("{}", e);
+                return signature::unsigned("the game list signature could not be fetched", policy);
+            }
+        };
+        signature::check(
+            body.as_bytes(),
+            signature.as_deref(),
+            self.public_key.as_deref(),
+            policy,
+        )
+    }
+}
+
+impl GameListSource {
+    fn describe(&self) -> String {
+        match self {
+            GameListSource::Http { url, .. } => url.clone(),
+            GameListSource::File { path, .. } => path.display().to_string(),
+            GameListSource::Cache => "cache".to_string(),
+            GameListSource::Bundled => "bundled".to_string(),
+        }
+    }
+}
+
+/// The GitHub mirror, then Discord, then the cache and the bundled list for offline use.
+pub fn default_sources() -> Vec<GameListSource> {
+    vec![
+        // Only signed when the build has a key to check it with, otherwise every fetch would be
+        // unsigned, and rejected under `UnsignedPolicy::Reject`.
+        GameListSource::Http {
+            url: GH_MIRROR_URL.to_string(),
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            retries: 1,
+            signature: SignatureConfig {
+                signed: signature::EMBEDDED_PUBLIC_KEY.is_some(),
+                public_key: None,
+            },
+        },
+        // Served by Discord itself, there is nothing to sign it.
+        GameListSource::Http {
+            url: DISCORD_URL.to_string(),
+            timeout_secs: DEFAULT_TIMEOUT_SECS,
+            retries: 1,
+            signature: SignatureConfig::default(),
+        },
+        GameListSource::Cache,
+        GameListSource::Bundled,
+    ]
+}
+
+fn configured_sources() -> Vec<GameListSource> {
+    settings::current()
+        .gamelist_sources
+        .unwrap_or_else(default_sources)
+}
+
+/// The last game list that was fetched successfully, saved to `gamelist.json` in the app data
+/// directory so it is still available offline.
+#[derive(Clone, Serialize, Deserialize)]
+struct CachedGameList {
+    #[serde(flatten)]
+    info: CacheInfo,
+    games: Vec<DetectableGame>,
+    rejected: usize,
+    /// What the last download changed compared to the list cached before it.
+    #[serde(default)]
+    changes: Option<GameListChanges>,
+    #[serde(default)]
+    signature: SignatureStatus,
+}
+
+/// Where the cached list came from and how old it is.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CacheInfo {
+    pub source: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// When the list was downloaded.
+    pub fetched: u64,
+    /// When the source last confirmed the list is current, which a `304 Not Modified` does
+    /// without downloading it again.
+    pub checked: u64,
+}
+
+/// A `GameListDiff` between two downloads, emitted as `gamelist_changed`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameListChanges {
+    #[serde(flatten)]
+    pub diff: GameListDiff,
+    pub previous_source: String,
+    pub previous_fetched: u64,
+    pub source: String,
+    pub fetched: u64,
+}
+
+/// A source that was skipped because it had no valid list.
+#[derive(Serialize)]
+pub struct SourceFailure {
+    pub source: String,
+    pub error: String,
+    pub attempts: u32,
+}
+
+/// What `fetch_gamelist` returns.
+#[derive(Serialize)]
+pub struct FetchedGameList {
+    #[serde(flatten)]
+    pub list: GameList,
+    /// Describes the source the list is from. Only HTTP sources have `etag` and
+    /// `last_modified`.
+    #[serde(flatten)]
+    pub info: CacheInfo,
+    /// Whether the list was served from the cache, because it was not modified or through the
+    /// cache source.
+    pub from_cache: bool,
+    pub signature: SignatureStatus,
+    /// The sources tried before this one.
+    pub failures: Vec<SourceFailure>,
+    /// Set when the list was just downloaded and differs from the one cached before it.
+    #[serde(skip)]
+    pub changes: Option<GameListChanges>,
+}
+
+static CACHE: OnceCell<Mutex<Option<CachedGameList>>> = OnceCell::new();
+
+fn cache_file_path() -> Result<PathBuf, String> {
+    Ok(app_dirs::get()?.data_dir.join("gamelist.json"))
+}
+
+fn read_cache() -> Option<CachedGameList> {
+    let file_path = cache_file_path().ok()?;
+    let json = fs::read_to_string(&file_path).ok()?;
+    serde_json::from_str(&json)
+        .map_err(|e| eprintln!("Failed to parse game list cache: {}", e))
+        .ok()
+}
+
+fn get_cache() -> &'static Mutex<Option<CachedGameList>> {
+    CACHE.get_or_init(|| Mutex::new(read_cache()))
+}
+
+fn write_cache(cached: &CachedGameList) -> Result<(), String> {
+    let file_path = cache_file_path()?;
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    }
+    let json = serde_json::to_string(cached)
+        .map_err(|e| format!("Failed to serialize game list cache: {}", e))?;
+
+    let temp_path = file_path.with_extension("json.tmp");
+    fs::write(&temp_path, json).map_err(|e| format!("Failed to write game list cache: {}", e))?;
+    fs::rename(&temp_path, &file_path)
+        .map_err(|e| format!("Failed to write game list cache: {}", e))
+}
+
+fn store(cached: CachedGameList) {
+    if let Err(e) = write_cache(&cached) {
+        eprintln!("{}", e);
+    }
+    *get_cache().lock().unwrap() = Some(cached);
+}
+
+/// Applies `policy` to a cached list, which may have been allowed under a laxer one. `config` is
+/// the signature config of the source being refreshed, if any.
+fn recheck_cached(
+    cached: &CachedGameList,
+    config: Option<&SignatureConfig>,
+    policy: UnsignedPolicy,
+) -> Result<SignatureStatus, String> {
+    match &cached.signature {
+        SignatureStatus::Verified => Ok(SignatureStatus::Verified),
+        SignatureStatus::Unsigned { reason } => signature::unsigned(reason, policy),
+        SignatureStatus::NotChecked if config.is_some_and(|config| config.signed) => {
+            signature::unsigned("the cached game list was never verified", policy)
+        }
+        SignatureStatus::NotChecked => Ok(SignatureStatus::NotChecked),
+    }
+}
+
+fn serve_cached(cached: CachedGameList) -> FetchedGameList {
+    FetchedGameList {
+        list: GameList {
+            games: cached.games,
+            rejected: cached.rejected,
+        },
+        info: cached.info,
+        from_cache: true,
+        signature: cached.signature,
+        failures: Vec::new(),
+        changes: None,
+    }
+}
+
+fn header_value(response: &reqwest::Response, name: header::HeaderName) -> Option<String> {
+    response
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+struct Download {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+/// Downloads the list at `url`, or returns `None` when the source answers `304 Not Modified`.
+/// The cache's validators are only sent when it came from the same `url`.
+async fn download(
+    client: &reqwest::Client,
+    url: &str,
+    cached: Option<&CachedGameList>,
+) -> Result<Option<Download>, String> {
+    let mut request = client.get(url);
+    if let Some(cached) = cached.filter(|cached| cached.info.source == url) {
+        if let Some(etag) = &cached.info.etag {
+            request = request.header(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.info.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch game list: {}", e))?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(format!("Failed to fetch game list: {}", response.status()));
+    }
+
+    let etag = header_value(&response, header::ETAG);
+    let last_modified = header_value(&response, header::LAST_MODIFIED);
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read game list: {}", e))?;
+
+    Ok(Some(Download {
+        etag,
+        last_modified,
+        body,
+    }))
+}
+
+/// Downloads the detached signature of the list at `url`, `None` if the source has none.
+async fn download_signature(client: &reqwest::Client, url: &str) -> Result<Option<String>, String> {
+    let response = client
+        .get(format!("{}{}", url, signature::SIGNATURE_SUFFIX))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch game list signature: {}", e))?;
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(format!(
+            "Failed to fetch game list signature: {}",
+            response.status()
+        ));
+    }
+
+    response
+        .text()
+        .await
+        .map(Some)
+        .map_err(|e| format!("Failed to read game list signature: {}", e))
+}
+
+/// Parses a loaded list, which is only usable if some of its entries are valid.
+fn parse_usable(body: &str) -> Result<GameList, String> {
+    let list = parse_game_list(body)?;
+    if list.games.is_empty() {
+        return Err("The game list has no valid entries".to_string());
+    }
+    Ok(list)
+}
+
+/// Refreshes the list from `url` and caches it, or confirms the cached copy is current.
+async fn fetch_http(
+    url: &str,
+    timeout: Duration,
+    signature_config: &SignatureConfig,
+    policy: UnsignedPolicy,
+) -> Result<FetchedGameList, String> {
+    let cached = get_cache().lock().unwrap().clone();
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+    let Some(download) = download(&client, url, cached.as_ref()).await? else {
+        let mut cached = cached
+            .filter(|cached| cached.info.source == url)
+            .ok_or("Not Modified, but there is no cached game list")?;
+        println!("Game list from {} is not modified", url);
+        cached.signature = recheck_cached(&cached, Some(signature_config), policy)?;
+        cached.info.checked = manifest::now_secs();
+        store(cached.clone());
+        return Ok(serve_cached(cached));
+    };
+
+    // Checked before parsing, the list decides which files are written and run.
+    let signature = match signature_config.wants_signature() {
+        true => download_signature(&client, url).await,
+        false => Ok(None),
+    };
+    let signature = signature_config.check(&download.body, signature, policy)?;
+
+    let list = parse_usable(&download.body)?;
+    let now = manifest::now_secs();
+    let info = CacheInfo {
+        source: url.to_string(),
+        etag: download.etag,
+        last_modified: download.last_modified,
+        fetched: now,
+        checked: now,
+    };
+    // The first download has nothing to compare against.
+    let changes = cached.map(|cached| GameListChanges {
+        diff: diff_game_lists(&cached.games, &list.games),
+        previous_source: cached.info.source,
+        previous_fetched: cached.info.fetched,
+        source: url.to_string(),
+        fetched: now,
+    });
+    let fresh_changes = changes.clone().filter(|changes| !changes.diff.is_empty());
+    if let Some(changes) = &fresh_changes {
+        println!(
+            "Game list changed: {} added, {} removed, {} changed",
+            changes.diff.added.len(),
+            changes.diff.removed.len(),
+            changes.diff.changed.len()
+        );
+    }
+
+    store(CachedGameList {
+        info: info.clone(),
+        games: list.games.clone(),
+        rejected: list.rejected,
+        changes,
+        signature: signature.clone(),
+    });
+
+    Ok(FetchedGameList {
+        list,
+        info,
+        from_cache: false,
+        signature,
+        failures: Vec::new(),
+        changes: fresh_changes,
+    })
+}
+
+fn read_file(
+    path: &Path,
+    source: String,
+    signature_config: &SignatureConfig,
+    policy: UnsignedPolicy,
+) -> Result<FetchedGameList, String> {
+    let body = fs::read_to_string(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    let signature = match signature_config.wants_signature() {
+        true => signature::read_signature_file(path),
+        false => Ok(None),
+    };
+    let signature = signature_config.check(&body, signature, policy)?;
+
+    let list = parse_usable(&body)?;
+    let modified = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|modified| modified.as_secs())
+        .unwrap_or_default();
+
+    Ok(FetchedGameList {
+        list,
+        info: CacheInfo {
+            source,
+            etag: None,
+            last_modified: None,
+            fetched: modified,
+            checked: manifest::now_secs(),
+        },
+        from_cache: false,
+        signature,
+        failures: Vec::new(),
+        changes: None,
+    })
+}
+
+/// Loads the list from `source`, returning how many attempts it took on failure. `policy` also
+/// applies to lists served from the cache.
+async fn load(
+    source: &GameListSource,
+    policy: UnsignedPolicy,
+) -> Result<FetchedGameList, (String, u32)> {
+    match source {
+        GameListSource::Http {
+            url,
+            timeout_secs,
+            retries,
+            signature,
+        } => {
+            let timeout = Duration::from_secs(*timeout_secs);
+            let mut attempt = 1;
+            loop {
+                match fetch_http(url, timeout, signature, policy).await {
+                    Ok(fetched) => return Ok(fetched),
+                    Err(e) if attempt > *retries => return Err((e, attempt)),
+                    Err(e) => {
+                        eprintln!("Attempt {} for {} failed: {}", attempt, url, e);
+                        tokio::time::sleep(RETRY_DELAY * attempt).await;
+                        attempt += 1;
+                    }
+                }
+            }
+        }
+        GameListSource::File { path, signature } => {
+            read_file(path, source.describe(), signature, policy).map_err(|e| (e, 1))
+        }
+        GameListSource::Cache => {
+            let mut cached = get_cache()
+                .lock()
+                .unwrap()
+                .clone()
+                .ok_or(("Nothing is cached yet".to_string(), 1))?;
+            cached.signature = recheck_cached(&cached, None, policy).map_err(|e| (e, 1))?;
+            Ok(serve_cached(cached))
+        }
+        GameListSource::Bundled => app_dirs::get()
+            .and_then(|dirs| {
+                read_file(
+                    &dirs.resource_dir.join(BUNDLED_RESOURCE_NAME),
+                    source.describe(),
+                    &SignatureConfig::default(),
+                    policy,
+                )
+            })
+            .map_err(|e| (e, 1)),
+    }
+}
+
+/// Tries `sources` in order and returns the first valid list, with the failures before it.
+pub async fn resolve(sources: &[GameListSource]) -> Result<FetchedGameList, Vec<SourceFailure>> {
+    let policy = settings::current().unsigned_gamelists;
+    let mut failures = Vec::new();
+    for source in sources {
+        match load(source, policy).await {
+            Ok(mut fetched) => {
+                println!(
+                    "Loaded {} games from {}, rejected {} entries",
+                    fetched.list.games.len(),
+                    source.describe(),
+                    fetched.list.rejected
+                );
+                fetched.failures = failures;
+                return Ok(fetched);
+            }
+            Err((error, attempts)) => {
+                eprintln!("Game list source {} failed: {}", source.describe(), error);
+                failures.push(SourceFailure {
+                    source: source.describe(),
+                    error,
+                    attempts,
+                });
+            }
+        }
+    }
+    Err(failures)
+}
+
+/// Loads the game list from the configured sources, see `set_gamelist_sources`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn fetch_gamelist(handle: AppHandle) -> Result<FetchedGameList, String> {
+    let fetched = resolve_configured().await?;
+    if let Some(changes) = &fetched.changes {
+        handle
+            .emit("gamelist_changed", changes)
+            .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
+    }
+    Ok(fetched)
+}
+
+/// Loads the game list from the configured sources and makes it the one `search_games` looks
+/// through. When none of them works, search falls back to the bundled list.
+pub async fn resolve_configured() -> Result<FetchedGameList, String> {
+    let sources = configured_sources();
+    match resolve(&sources).await {
+        Ok(fetched) => {
+            search::set_games(&fetched.list.games);
+            Ok(fetched)
+        }
+        Err(failures) => {
+            if !sources
+                .iter()
+                .any(|source| matches!(source, GameListSource::Bundled))
+            {
+                index_bundled().await;
+            }
+            let reasons: Vec<String> = failures
+                .iter()
+                .map(|failure| format!("{}: {}", failure.source, failure.error))
+                .collect();
+            Err(format!(
+                "No game list source worked. {}",
+                reasons.join("; ")
+            ))
+        }
+    }
+}
+
+/// Fills the search index from the bundled list, for when the configured sources leave it out
+/// and all of them failed.
+async fn index_bundled() {
+    match load(&GameListSource::Bundled, UnsignedPolicy::default()).await {
+        Ok(fetched) => {
+            println!(
+                "Searching the bundled game list, {} games",
+                fetched.list.games.len()
+            );
+            search::set_games(&fetched.list.games);
+        }
+        Err((e, _)) => eprintln!("Failed to load the bundled game list: {}", e),
+    }
+}
+
+/// Reports where the cached game list came from and when, `None` when nothing is cached.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_gamelist_cache_info() -> Option<CacheInfo> {
+    get_cache()
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|cached| cached.info.clone())
+}
+
+/// Reports what the last download of the game list added, removed and changed compared to
+/// the list before it. `None` until a list was downloaded twice.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_gamelist_changes() -> Option<GameListChanges> {
+    get_cache()
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|cached| cached.changes.clone())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_gamelist_sources() -> serde_json::Value {
+    serde_json::json!({
+        "sources": configured_sources(),
+        "is_default": settings::current().gamelist_sources.is_none(),
+    })
+}
+
+/// Changes where `fetch_gamelist` looks for the game list and in which order, `None` goes back
+/// to the defaults.
+#[tauri::command(rename_all = "snake_case")]
+pub fn set_gamelist_sources(
+    sources: Option<Vec<GameListSource>>,
+) -> Result<serde_json::Value, String> {
+    if let Some(sources) = &sources {
+        if sources.is_empty() {
+            return Err("At least one game list source is needed".to_string());
+        }
+        for source in sources {
+            match source {
+                GameListSource::Http { url, .. }
+                    if !url.starts_with("https://") && !url.starts_with("http://") =>
+                {
+                    return Err(format!("{:?} is not an HTTP URL", url));
+                }
+                GameListSource::File { path, .. } if !path.is_absolute() => {
+                    return Err(format!(
+                        "Game list file {:?} must be an absolute path",
+                        path
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    settings::update(|settings| settings.gamelist_sources = sources)?;
+    Ok(get_gamelist_sources())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const LIST: &str =
+        r#"[{"id":"1","name":"Game","executables":[{"os":"win32","name":"game.exe"}]}]"#;
+
+    // A fixed test keypair, never used to sign real lists.
+    fn test_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    /// Writes `LIST` to a new folder, with a signature of `signed_body` when given.
+    fn write_list(name: &str, signed_body: Option<&str>) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("gamelist-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("detectable.json");
+        fs::write(&path, LIST).unwrap();
+        if let Some(body) = signed_body {
+            let signature = STANDARD.encode(test_key().sign(body.as_bytes()).to_bytes());
+            fs::write(signature::signature_path(&path), signature).unwrap();
+        }
+        path
+    }
+
+    fn file_source(path: &Path, public_key: Option<String>) -> GameListSource {
+        GameListSource::File {
+            path: path.to_path_buf(),
+            signature: SignatureConfig {
+                signed: true,
+                public_key,
+            },
+        }
+    }
+
+    fn test_public_key() -> Option<String> {
+        Some(STANDARD.encode(test_key().verifying_key().as_bytes()))
+    }
+
+    fn cached(signature: SignatureStatus) -> CachedGameList {
+        CachedGameList {
+            info: CacheInfo {
+                source: GH_MIRROR_URL.to_string(),
+                etag: None,
+                last_modified: None,
+                fetched: 0,
+                checked: 0,
+            },
+            games: Vec::new(),
+            rejected: 0,
+            changes: None,
+            signature,
+        }
+    }
+
+    #[tokio::test]
+    async fn loads_a_signed_file() {
+        let path = write_list("signed", Some(LIST));
+        let source = file_source(&path, test_public_key());
+
+        let fetched = load(&source, UnsignedPolicy::Reject).await;
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        let fetched = fetched.unwrap_or_else(|(e, _)| panic!("{}", e));
+        assert!(matches!(fetched.signature, SignatureStatus::Verified));
+        assert_eq!(fetched.list.games.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_a_file_signed_for_another_list() {
+        let path = write_list("tampered", Some("[]"));
+        let source = file_source(&path, test_public_key());
+
+        let fetched = load(&source, UnsignedPolicy::Warn).await;
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        let (error, attempts) = fetched.err().unwrap();
+        assert_eq!(error, "Invalid game list signature");
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn unsigned_file_follows_the_policy() {
+        let path = write_list("unsigned", None);
+        let source = file_source(&path, test_public_key());
+
+        let rejected = load(&source, UnsignedPolicy::Reject).await;
+        let warned = load(&source, UnsignedPolicy::Warn).await;
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert!(rejected.is_err());
+        assert!(matches!(
+            warned.map(|fetched| fetched.signature),
+            Ok(SignatureStatus::Unsigned { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn unreadable_signature_counts_as_missing() {
+        let path = write_list("unreadable", None);
+        // Reading a folder fails, unlike a signature that isn't there.
+        fs::create_dir(signature::signature_path(&path)).unwrap();
+        let source = file_source(&path, test_public_key());
+
+        let rejected = load(&source, UnsignedPolicy::Reject).await;
+        let warned = load(&source, UnsignedPolicy::Warn).await;
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        assert!(rejected.is_err());
+        let Ok(SignatureStatus::Unsigned { reason }) = warned.map(|fetched| fetched.signature)
+        else {
+            panic!("expected an unsigned list");
+        };
+        assert_eq!(reason, "the game list signature could not be fetched");
+    }
+
+    #[tokio::test]
+    async fn signature_is_not_read_without_a_public_key() {
+        if signature::EMBEDDED_PUBLIC_KEY.is_some() {
+            return;
+        }
+        let path = write_list("no-key", None);
+        fs::create_dir(signature::signature_path(&path)).unwrap();
+        let source = file_source(&path, None);
+
+        let warned = load(&source, UnsignedPolicy::Warn).await;
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        let Ok(SignatureStatus::Unsigned { reason }) = warned.map(|fetched| fetched.signature)
+        else {
+            panic!("expected an unsigned list");
+        };
+        assert!(reason.contains("no public key"), "{}", reason);
+    }
+
+    #[test]
+    fn default_sources_can_be_verified_under_every_policy() {
+        for source in default_sources() {
+            let GameListSource::Http { url, signature, .. } = source else {
+                continue;
+            };
+            // A signed source without a key could never be verified.
+            assert_eq!(signature.signed, signature.wants_signature(), "{}", url);
+            if !signature.signed {
+                for policy in [UnsignedPolicy::default(), UnsignedPolicy::Reject] {
+                    assert!(matches!(
+                        signature.check("[]", Ok(None), policy),
+                        Ok(SignatureStatus::NotChecked)
+                    ));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cached_lists_follow_the_current_policy() {
+        let signed = SignatureConfig {
+            signed: true,
+            public_key: None,
+        };
+        let unsigned = cached(SignatureStatus::Unsigned {
+            reason: "the game list signature is missing".to_string(),
+        });
+        assert!(recheck_cached(&unsigned, None, UnsignedPolicy::Reject).is_err());
+        assert!(recheck_cached(&unsigned, Some(&signed), UnsignedPolicy::Reject).is_err());
+        assert!(recheck_cached(&unsigned, None, UnsignedPolicy::Warn).is_ok());
+
+        let verified = cached(SignatureStatus::Verified);
+        assert!(recheck_cached(&verified, Some(&signed), UnsignedPolicy::Reject).is_ok());
+
+        // Cached from a source that wasn't signed back then.
+        let not_checked = cached(SignatureStatus::NotChecked);
+        assert!(recheck_cached(&not_checked, Some(&signed), UnsignedPolicy::Reject).is_err());
+        assert!(recheck_cached(&not_checked, None, UnsignedPolicy::Reject).is_ok());
+    }
+}
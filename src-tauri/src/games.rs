@@ -0,0 +1,194 @@
+use crate::app_dirs;
+use crate::engine::{GcReport, InstalledGame, UpgradeReport};
+use crate::settings;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Resolves the folder fake games are installed into.
+///
+/// Uses the `games_root` setting when set, otherwise `games` in the platform app data
+/// directory, which is under `$XDG_DATA_HOME` on Linux.
+pub fn games_root() -> Result<PathBuf, String> {
+    if let Some(games_root) = settings::current().games_root {
+        return Ok(games_root);
+    }
+
+    Ok(app_dirs::get()?.data_dir.join("games"))
+}
+
+/// Where older versions installed games, next to the executable. This is read-only for
+/// AppImage, Flatpak and system-wide installs.
+pub fn legacy_games_root() -> Option<PathBuf> {
+    let exe_path = env::current_exe().ok()?;
+    Some(exe_path.parent()?.join("games"))
+}
+
+fn copy_dir_all(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves a directory, falling back to copy and delete when `to` is on another filesystem.
+fn move_dir(from: &Path, to: &Path) -> Result<(), String> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+
+    copy_dir_all(from, to).map_err(|e| {
+        let _ = fs::remove_dir_all(to);
+        format!("Failed to copy {:?} to {:?}: {}", from, to, e)
+    })?;
+    fs::remove_dir_all(from).map_err(|e| format!("Failed to remove {:?}: {}", from, e))
+}
+
+/// Moves every game folder from `from` into `to`. Games already present in `to` are left where
+/// they are and reported as skipped.
+fn migrate_games(from: &Path, to: &Path) -> Result<serde_json::Value, String> {
+    let mut migrated = Vec::new();
+    let mut skipped = Vec::new();
+
+    if from.is_dir() {
+        fs::create_dir_all(to).map_err(|e| format!("Failed to create games root: {}", e))?;
+
+        let entries =
+            fs::read_dir(from).map_err(|e| format!("Failed to read {:?}: {}", from, e))?;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let target = to.join(&name);
+            if target.exists() {
+                println!("Skipping {:?}, it already exists in {:?}", name, to);
+                skipped.push(name);
+                continue;
+            }
+
+            move_dir(&entry.path(), &target)?;
+            println!("Migrated {:?} to {:?}", entry.path(), target);
+            migrated.push(name);
+        }
+
+        // Only succeeds once everything has moved out.
+        let _ = fs::remove_dir(from);
+    }
+
+    Ok(serde_json::json!({
+        "from": from,
+        "to": to,
+        "migrated": migrated,
+        "skipped": skipped,
+    }))
+}
+
+/// Reports the executables installed for each app_id, with their size, creation time and
+/// whether a runner started from them is running.
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_installed_games(app_id: Option<i64>) -> Result<Vec<InstalledGame>, String> {
+    Ok(crate::installer()?.installed(crate::get_runner_manager(), app_id))
+}
+
+/// Removes an installed game, or only one of its executables when `executable` is given as
+/// the detectable list names it, for example `bin/game.exe`. Runners started from it are
+/// stopped first.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn uninstall_fake_game(
+    app_id: i64,
+    executable: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let removed = crate::installer()?.uninstall(
+        crate::get_runner_manager(),
+        app_id,
+        executable.as_deref(),
+    )?;
+
+    Ok(serde_json::json!({
+        "app_id": app_id,
+        "removed": removed,
+    }))
+}
+
+/// Cleans up the games root: deletes games whose runners were not launched in the last
+/// `max_age_days` days, runner copies that differ from the bundled template, and empty folders.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn gc_games(max_age_days: Option<u64>) -> Result<GcReport, String> {
+    let max_age = max_age_days.map(|days| Duration::from_secs(days * 24 * 60 * 60));
+
+    crate::installer()?.gc(crate::get_runner_manager(), max_age)
+}
+
+/// Replaces installed runners that differ from the bundled template with a fresh copy of it.
+/// Runners that are running are skipped, they get upgraded the next time this runs.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn upgrade_runners() -> Result<UpgradeReport, String> {
+    crate::installer()?.upgrade(crate::get_runner_manager())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_games_root() -> Result<serde_json::Value, String> {
+    Ok(serde_json::json!({
+        "games_root": games_root()?,
+        "is_default": settings::current().games_root.is_none(),
+        "legacy_games_root": legacy_games_root(),
+    }))
+}
+
+/// Changes where games are installed, `None` goes back to the default. Existing installs stay
+/// where they are until `migrate_games_root` moves them.
+#[tauri::command(rename_all = "snake_case")]
+pub fn set_games_root(games_root: Option<String>) -> Result<serde_json::Value, String> {
+    let games_root = games_root
+        .filter(|root| !root.is_empty())
+        .map(PathBuf::from);
+    if let Some(root) = &games_root {
+        if !root.is_absolute() {
+            return Err(format!("Games root {:?} must be an absolute path", root));
+        }
+        fs::create_dir_all(root).map_err(|e| format!("Failed to create games root: {}", e))?;
+    }
+
+    settings::update(|settings| settings.games_root = games_root)?;
+    get_games_root()
+}
+
+/// Moves games installed by older versions next to the executable, or from `from` if given,
+/// into the current games root.
+#[tauri::command(rename_all = "snake_case")]
+pub fn migrate_games_root(from: Option<String>) -> Result<serde_json::Value, String> {
+    let runners = crate::get_runner_manager();
+    if runners.has_running() {
+        return Err("Stop all running games before migrating them".to_string());
+    }
+
+    let from = match from {
+        Some(from) => PathBuf::from(from),
+        None => legacy_games_root().ok_or("Failed to resolve the executable directory")?,
+    };
+    // Either folder may not exist yet, or be reached through a symlink.
+    let from = fs::canonicalize(&from).unwrap_or(from);
+    let to = games_root()?;
+    let to = fs::canonicalize(&to).unwrap_or(to);
+    if to.starts_with(&from) {
+        return Err(format!("The games root {:?} is inside {:?}", to, from));
+    }
+    if from.starts_with(&to) {
+        return Err(format!("{:?} is inside the games root {:?}", from, to));
+    }
+
+    // Runners started by another process, like the command line, are only in the manifests.
+    for root in [&from, &to] {
+        if !runners.recorded(&crate::installer_at(root.clone())?).is_empty() {
+            return Err("Stop all running games before migrating them".to_string());
+        }
+    }
+
+    migrate_games(&from, &to)
+}
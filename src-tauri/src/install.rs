@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// How runner copies are placed in the games root.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallStrategy {
+    /// Hard link, then reflink, then copy, whichever works first.
+    #[default]
+    Auto,
+    Hardlink,
+    Reflink,
+    Copy,
+}
+
+/// What was actually used to place a runner.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallMethod {
+    Hardlink,
+    Reflink,
+    Copy,
+}
+
+impl InstallStrategy {
+    fn methods(self) -> &'static [InstallMethod] {
+        match self {
+            InstallStrategy::Auto => &[
+                InstallMethod::Hardlink,
+                InstallMethod::Reflink,
+                InstallMethod::Copy,
+            ],
+            InstallStrategy::Hardlink => &[InstallMethod::Hardlink, InstallMethod::Copy],
+            InstallStrategy::Reflink => &[InstallMethod::Reflink, InstallMethod::Copy],
+            InstallStrategy::Copy => &[InstallMethod::Copy],
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(0o755);
+    fs::set_permissions(path, permissions)
+}
+
+fn hard_link(template: &Path, target: &Path) -> io::Result<()> {
+    // A hard link shares the template's permissions, which may belong to another user for
+    // system-wide installs, so only link a template that is already executable.
+    #[cfg(unix)]
+    if !is_executable(template) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "the runner template is not executable",
+        ));
+    }
+
+    fs::hard_link(template, target)
+}
+
+/// Clones the template's extents with `FICLONE`, which btrfs and xfs support.
+#[cfg(target_os = "linux")]
+fn reflink(template: &Path, target: &Path) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let source = fs::File::open(template)?;
+    let destination = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)?;
+
+    let (source_fd, destination_fd) = (source.as_raw_fd(), destination.as_raw_fd());
+    // SAFETY: both descriptors stay open for the duration of the call.
+    let result = unsafe { libc::ioctl(destination_fd, libc::FICLONE, source_fd) };
+    if result == -1 {
+        let error = io::Error::last_os_error();
+        drop(destination);
+        let _ = fs::remove_file(target);
+        return Err(error);
+    }
+
+    make_executable(target)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink(_template: &Path, _target: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "reflinks are only supported on Linux",
+    ))
+}
+
+fn copy(template: &Path, target: &Path) -> io::Result<()> {
+    fs::copy(template, target)?;
+
+    #[cfg(unix)]
+    make_executable(target)?;
+
+    Ok(())
+}
+
+/// Places the runner template at `target`, which must not exist yet.
+fn place_new_runner(
+    template: &Path,
+    target: &Path,
+    strategy: InstallStrategy,
+) -> Result<InstallMethod, String> {
+    let mut last_error = None;
+    for &method in strategy.methods() {
+        let placed = match method {
+            InstallMethod::Hardlink => hard_link(template, target),
+            InstallMethod::Reflink => reflink(template, target),
+            InstallMethod::Copy => copy(template, target),
+        };
+        match placed {
+            Ok(()) => return Ok(method),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(format!(
+        "Failed to copy dummy executable: {}",
+        last_error.map(|e| e.to_string()).unwrap_or_default()
+    ))
+}
+
+/// Places the runner template at `target` using the first method of `strategy` that works.
+///
+/// Every method gives the runner its own file name, which Discord's detection needs.
+pub fn place_runner(
+    template: &Path,
+    target: &Path,
+    strategy: InstallStrategy,
+) -> Result<InstallMethod, String> {
+    // Links can't replace an existing file, and a reinstall must not write through an old
+    // hard link into the template.
+    if target.is_file() {
+        return replace_runner(template, target, strategy);
+    }
+
+    place_new_runner(template, target, strategy)
+}
+
+const UPGRADE_SUFFIX: &str = ".upgrade";
+
+/// Whether `name` is a copy `replace_runner` left behind when it was interrupted.
+pub fn is_upgrade_temp_file(name: &str) -> bool {
+    name.starts_with('.') && name.ends_with(UPGRADE_SUFFIX)
+}
+
+/// Replaces the runner at `target` with a new copy of the template. The copy is placed next to
+/// it and renamed over it, so `target` is never missing or half written.
+pub fn replace_runner(
+    template: &Path,
+    target: &Path,
+    strategy: InstallStrategy,
+) -> Result<InstallMethod, String> {
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| format!("{:?} is not a file", target))?;
+    let temp_path = target.with_file_name(format!(
+        ".{}{}",
+        file_name.to_string_lossy(),
+        UPGRADE_SUFFIX
+    ));
+
+    // Left behind when an earlier replace was interrupted.
+    if temp_path.is_file() {
+        fs::remove_file(&temp_path)
+            .map_err(|e| format!("Failed to remove {:?}: {}", temp_path, e))?;
+    }
+
+    let method = place_new_runner(template, &temp_path, strategy)?;
+    fs::rename(&temp_path, target).map_err(|e| {
+        let _ = fs::remove_file(&temp_path);
+        format!("Failed to replace {:?}: {}", target, e)
+    })?;
+
+    Ok(method)
+}
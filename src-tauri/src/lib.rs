@@ -1,19 +1,43 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+use engine::{GameInstaller, InstallerConfig, PresenceManager, RunnerManager, RunnerProcess};
 use once_cell::sync::OnceCell;
-use std::env;
-use std::fs;
-use std::path::{Path, PathBuf};
-use std::sync::Mutex;
-use tauri::{path::BaseDirectory, AppHandle, Emitter, Listener, Manager};
-
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Listener};
+
+mod app_dirs;
+pub mod cli;
+mod detectable;
+pub mod engine;
+mod gamelist;
+mod games;
+mod install;
+mod manifest;
+mod paths;
+mod queue;
 mod rpc;
 mod runner;
+mod search;
+mod settings;
+mod signature;
+
+// Connected Discord clients, so several apps can show presence at once
+static PRESENCE: OnceCell<PresenceManager> = OnceCell::new();
+
+fn get_presence_manager() -> &'static PresenceManager {
+    PRESENCE.get_or_init(PresenceManager::new)
+}
+
+// Bumped by the legacy `event_disconnect` event, so `connect_to_discord_rpc_3` calls that are
+// still connecting when it fires drop their client once they finish.
+static LEGACY_DISCONNECT_GENERATION: AtomicU64 = AtomicU64::new(0);
 
-// Global static instance of the Discord client
-static DISCORD_CLIENT: OnceCell<Mutex<Option<rpc::Client>>> = OnceCell::new();
+// Every runner the app launched. Stopping a game only ever targets runners in here.
+static RUNNERS: OnceCell<RunnerManager> = OnceCell::new();
 
-fn get_discord_client() -> &'static Mutex<Option<rpc::Client>> {
-    DISCORD_CLIENT.get_or_init(|| Mutex::new(None))
+fn get_runner_manager() -> &'static RunnerManager {
+    RUNNERS.get_or_init(RunnerManager::new)
 }
 
 fn runner_resource_name() -> &'static str {
@@ -29,106 +53,159 @@ fn runner_resource_name() -> &'static str {
     runner_name
 }
 
-fn is_app_bundle(executable_name: &str) -> bool {
-    cfg!(target_os = "macos") && executable_name.ends_with(".app")
-}
+// The runner template is bundled with the app, so it shares the app's version.
+const RUNNER_TEMPLATE_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-fn bundle_binary_name(app_name: &str) -> String {
-    app_name
-        .strip_suffix(".app")
-        .unwrap_or(app_name)
-        .to_string()
+/// Sets up a `GameInstaller` for the games root and install strategy in the settings, with the
+/// runner bundled with the app as its template.
+fn installer() -> Result<GameInstaller, String> {
+    installer_at(games::games_root()?)
 }
 
-fn game_folder_path(exe_dir: &Path, path: &str, app_id: i64) -> PathBuf {
-    let normalized_path = Path::new(path).to_string_lossy().to_string();
+/// An installer for the games under `games_root` rather than the configured games root.
+fn installer_at(games_root: PathBuf) -> Result<GameInstaller, String> {
+    Ok(GameInstaller::new(InstallerConfig {
+        games_root,
+        template_path: app_dirs::get()?.resource_dir.join(runner_resource_name()),
+        template_version: RUNNER_TEMPLATE_VERSION.to_string(),
+        install_strategy: settings::current().install_strategy,
+    }))
+}
 
-    exe_dir
-        .join("games")
-        .join(app_id.to_string())
-        .join(normalized_path)
+fn runner_payload(runner: &RunnerProcess) -> serde_json::Value {
+    serde_json::json!({
+        "app_id": runner.app_id,
+        "executable_name": runner.executable_name,
+        "executable_path": runner.executable_path,
+        "pid": runner.pid,
+    })
 }
 
-fn resolve_runner_template(handle: &AppHandle) -> Result<PathBuf, String> {
+/// Launches an installed runner and reports how it ends.
+///
+/// Emits `runner_started`, then `runner_exited` for every exit, followed by `runner_crashed`
+/// when the runner died on its own with a failure code or a signal.
+///
+/// Returns the runner's PID, or `None` for macOS app bundles which are launched through `open`.
+fn launch_runner(
+    handle: &AppHandle,
+    name: &str,
+    path: &str,
+    executable_name: &str,
+    app_id: i64,
+) -> Result<Option<u32>, String> {
+    let exit_handle = handle.clone();
+    let on_exit = move |exit: engine::RunnerExit| {
+        let mut payload = runner_payload(&exit.runner);
+        payload["code"] = serde_json::json!(exit.code);
+        payload["signal"] = serde_json::json!(exit.signal);
+        payload["requested"] = serde_json::json!(exit.runner.stop_requested);
+        if let Some(error) = &exit.error {
+            payload["error"] = serde_json::json!(error);
+        }
+
+        exit_handle
+            .emit("runner_exited", payload.clone())
+            .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
+
+        if exit.crashed() {
+            exit_handle
+                .emit("runner_crashed", payload)
+                .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
+        }
+    };
+
+    let launched =
+        get_runner_manager().launch(&installer()?, name, path, executable_name, app_id, on_exit)?;
+    let Some(runner) = launched else {
+        return Ok(None);
+    };
+
     handle
-        .path()
-        .resolve(runner_resource_name(), BaseDirectory::Resource)
-        .map_err(|e| format!("Failed to resolve runner template: {}", e))
+        .emit("runner_started", runner_payload(&runner))
+        .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
+
+    Ok(Some(runner.pid))
 }
 
-fn make_macos_app_bundle(
-    app_bundle_path: &Path,
-    app_name: &str,
-    display_name: &str,
-    runner_template: &Path,
-) -> Result<PathBuf, String> {
-    let binary_name = bundle_binary_name(app_name);
-    let macos_dir = app_bundle_path.join("Contents/MacOS");
-    fs::create_dir_all(&macos_dir)
-        .map_err(|e| format!("Failed to create app bundle directories: {}", e))?;
-
-    let target_executable_path = macos_dir.join(&binary_name);
-    fs::copy(runner_template, &target_executable_path)
-        .map_err(|e| format!("Failed to copy dummy executable: {}", e))?;
-
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut permissions = fs::metadata(&target_executable_path)
-            .map_err(|e| format!("Failed to read executable permissions: {}", e))?
-            .permissions();
-        permissions.set_mode(0o755);
-        fs::set_permissions(&target_executable_path, permissions)
-            .map_err(|e| format!("Failed to set executable permissions: {}", e))?;
-    }
+/// Runs a timed quest session. Emits `session_tick` every second and, once `duration` has
+/// elapsed, stops the runner and clears the Discord activity for the same app before emitting
+/// `session_completed`. If the runner exits or is stopped first, the activity is cleared all the
+/// same and `session_ended` is emitted instead.
+///
+/// `pid` is `None` for macOS app bundles, which are launched through `open` and aren't tracked.
+fn start_session_timer(
+    handle: AppHandle,
+    app_id: i64,
+    executable_name: String,
+    pid: Option<u32>,
+    duration: Duration,
+) {
+    tauri::async_runtime::spawn(async move {
+        let started = Instant::now();
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+
+        loop {
+            ticker.tick().await;
+
+            if let Some(pid) = pid {
+                if !get_runner_manager().is_active(pid) {
+                    println!("Runner pid {} ended before its session timer", pid);
+                    if let Err(e) = get_presence_manager().end_session(app_id).await {
+                        emit_client_error(&handle, Some(&app_id.to_string()), &e);
+                    }
+
+                    let ended_payload = serde_json::json!({
+                        "app_id": app_id,
+                        "executable_name": executable_name,
+                        "pid": pid,
+                        "duration_secs": duration.as_secs(),
+                        "elapsed_secs": started.elapsed().as_secs(),
+                    });
+                    handle
+                        .emit("session_ended", ended_payload)
+                        .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
+                    return;
+                }
+            }
 
-    let info_plist = format!(
-        r#"<?xml version="1.0" encoding="UTF-8"?>
-<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
-<plist version="1.0">
-<dict>
-    <key>CFBundleDisplayName</key>
-    <string>{display_name}</string>
-    <key>CFBundleExecutable</key>
-    <string>{binary_name}</string>
-    <key>CFBundleIdentifier</key>
-    <string>me.markterence.discordquestcompleter.dummy</string>
-    <key>CFBundleName</key>
-    <string>{display_name}</string>
-    <key>CFBundlePackageType</key>
-    <string>APPL</string>
-    <key>CFBundleShortVersionString</key>
-    <string>1.0</string>
-    <key>CFBundleVersion</key>
-    <string>1</string>
-</dict>
-</plist>
-"#
-    );
-
-    fs::write(app_bundle_path.join("Contents/Info.plist"), info_plist)
-        .map_err(|e| format!("Failed to write Info.plist: {}", e))?;
-
-    Ok(target_executable_path)
-}
+            let elapsed = started.elapsed();
+            if elapsed >= duration {
+                break;
+            }
 
-fn launch_executable_path(game_folder_path: &Path, executable_name: &str) -> PathBuf {
-    if is_app_bundle(executable_name) {
-        game_folder_path
-            .join(executable_name)
-            .join("Contents/MacOS")
-            .join(bundle_binary_name(executable_name))
-    } else {
-        game_folder_path.join(executable_name)
-    }
-}
+            let tick_payload = serde_json::json!({
+                "app_id": app_id,
+                "executable_name": executable_name,
+                "pid": pid,
+                "duration_secs": duration.as_secs(),
+                "elapsed_secs": elapsed.as_secs(),
+                "remaining_secs": (duration - elapsed).as_secs(),
+            });
+            handle
+                .emit("session_tick", tick_payload)
+                .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
+        }
 
-fn stop_process_name(exec_name: &str) -> String {
-    if is_app_bundle(exec_name) {
-        bundle_binary_name(exec_name)
-    } else {
-        exec_name.to_string()
-    }
+        println!("Session for {} (app_id {}) is up", executable_name, app_id);
+
+        let stopped = get_runner_manager().stop_pid(pid, &executable_name);
+
+        if let Err(e) = get_presence_manager().end_session(app_id).await {
+            emit_client_error(&handle, Some(&app_id.to_string()), &e);
+        }
+
+        let completed_payload = serde_json::json!({
+            "app_id": app_id,
+            "executable_name": executable_name,
+            "pid": pid,
+            "duration_secs": duration.as_secs(),
+            "error": stopped.err(),
+        });
+        handle
+            .emit("session_completed", completed_payload)
+            .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
+    });
 }
 
 #[tauri::command]
@@ -138,301 +215,244 @@ fn greet(name: &str) -> String {
 
 #[tauri::command(rename_all = "snake_case")]
 async fn create_fake_game(
-    handle: tauri::AppHandle,
     path: &str,
     executable_name: &str,
     path_len: i64,
     app_id: i64,
     display_name: Option<String>,
-) -> Result<String, String> {
-    // Must create in the same directory as the executable to avoid permission issues
-    // Get the executable directory to look for config file
-    let exe_path: std::path::PathBuf = env::current_exe().unwrap_or_default();
-    let exe_dir = exe_path.parent().unwrap_or_else(|| Path::new(""));
-
-    let game_folder_path = game_folder_path(exe_dir, path, app_id);
-
-    println!("Game folder path: {:?}", game_folder_path);
-    println!(
-        "Game full path: {:?}",
-        game_folder_path.join(executable_name)
-    );
-
-    match fs::create_dir_all(&game_folder_path) {
-        Ok(_) => {
-            println!("Successfully created directory: {:?}", game_folder_path);
-        }
-        Err(e) => return Err(format!("Failed to create game folder: {}", e)),
-    };
-
-    let resource_path = resolve_runner_template(&handle)?;
-    println!("Creating dummy game executable from: {:?}", resource_path);
-
-    if is_app_bundle(executable_name) {
-        let app_bundle_path = game_folder_path.join(executable_name);
-        let bundle_display_name = display_name
-            .filter(|name| !name.is_empty())
-            .unwrap_or_else(|| bundle_binary_name(executable_name));
-        let target_executable_path = make_macos_app_bundle(
-            &app_bundle_path,
-            executable_name,
-            &bundle_display_name,
-            &resource_path,
-        )?;
-        return Ok(format!(
-            "Dummy app bundle created at: {:?}",
-            target_executable_path
-        ));
-    }
-
-    let target_executable_path = game_folder_path.join(executable_name);
-    match fs::copy(&resource_path, &target_executable_path) {
-        Ok(_) => {
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                let mut perms = std::fs::metadata(&target_executable_path)
-                    .map_err(|e| format!("Failed to get file metadata: {}", e))?
-                    .permissions();
-                perms.set_mode(0o755);
-                std::fs::set_permissions(&target_executable_path, perms)
-                    .map_err(|e| format!("Failed to set executable permissions: {}", e))?;
-            }
-
-            Ok(format!(
-                "Dummy executable copied to: {:?}",
-                target_executable_path
-            ))
-        }
-        Err(e) => Err(format!("Failed to copy dummy executable: {}", e)),
-    }
-}
-
-#[cfg(target_os = "macos")]
-fn launch_macos_app_bundle(app_bundle_path: &Path, title: &str) -> Result<(), String> {
-    let mut command = std::process::Command::new("open");
-    command
-        .arg("-n")
-        .arg("-g")
-        .arg("-a")
-        .arg(app_bundle_path)
-        .arg("--args")
-        .arg("--title")
-        .arg(title);
-
-    command
-        .spawn()
-        .map_err(|e| format!("Failed to launch app bundle with open: {}", e))?;
-
-    Ok(())
+    executable: Option<detectable::DetectableExecutable>,
+) -> Result<engine::InstalledRunner, String> {
+    installer()?.install(
+        get_runner_manager(),
+        path,
+        executable_name,
+        app_id,
+        display_name,
+        executable.as_ref(),
+    )
 }
 
 #[tauri::command(rename_all = "snake_case")]
 async fn run_background_process(
+    handle: AppHandle,
     name: &str,
     path: &str,
     executable_name: &str,
     path_len: i64,
     app_id: i64,
+    duration_secs: Option<u64>,
 ) -> Result<String, String> {
-    let exe_path = env::current_exe().unwrap_or_default();
-    let exe_dir = exe_path.parent().unwrap_or_else(|| Path::new(""));
-
-    let game_folder_path = game_folder_path(exe_dir, path, app_id);
-
-    if is_app_bundle(executable_name) {
-        #[cfg(target_os = "macos")]
-        {
-            let bundle_path = game_folder_path.join(executable_name);
-            launch_macos_app_bundle(&bundle_path, name)?;
-            return Ok("App bundle launched successfully".to_string());
-        }
-
-        #[cfg(not(target_os = "macos"))]
-        {
-            let _ = name;
-            return Err("App bundle launches are only supported on macOS".to_string());
-        }
+    let pid = launch_runner(&handle, name, path, executable_name, app_id)?;
+
+    if let Some(duration_secs) = duration_secs {
+        start_session_timer(
+            handle,
+            app_id,
+            executable_name.to_string(),
+            pid,
+            Duration::from_secs(duration_secs),
+        );
     }
 
-    let executable_path = launch_executable_path(&game_folder_path, executable_name);
-    
-    let mut cmd = std::process::Command::new(&executable_path);
-    cmd.args(["--title", name])
-       .current_dir(game_folder_path);
-    
-    // Platform-specific process spawning
-    #[cfg(unix)]
-    {
-        use std::os::unix::process::CommandExt;
-        cmd.process_group(0); // Create new process group on Unix
-    }
-    
-    match cmd.spawn() {
-        Ok(_) => Ok("Process started successfully".to_string()),
-        Err(e) => Err(format!("Failed to start process: {}", e)),
+    match pid {
+        Some(_) => Ok("Process started successfully".to_string()),
+        None => Ok("App bundle launched successfully".to_string()),
     }
 }
 
 #[tauri::command(rename_all = "snake_case")]
-async fn stop_process(exec_name: String) -> Result<(), String> {
-    let process_name = stop_process_name(&exec_name);
-
-    #[cfg(target_os = "windows")]
-    {
-        let output = std::process::Command::new("taskkill")
-            .arg("/F")
-            .arg("/IM")
-            .arg(&process_name)
-            .output()
-            .map_err(|e| format!("Failed to execute taskkill: {}", e))?;
-
-        if output.status.success() {
-            Ok(())
-        } else {
-            Err(format!(
-                "Failed to stop process: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ))
-        }
-    }
+async fn stop_process(exec_name: String, app_id: Option<i64>) -> Result<(), String> {
+    get_runner_manager().stop(&installer()?, &exec_name, app_id)
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        if is_app_bundle(&exec_name) {
-            let bundle_pattern = format!("{}/Contents/MacOS", exec_name);
-            let output = std::process::Command::new("pkill")
-                .arg("-f")
-                .arg(&bundle_pattern)
-                .output()
-                .map_err(|e| format!("Failed to execute pkill: {}", e))?;
-
-            if output.status.success() {
-                return Ok(());
-            }
-        }
+/// Tells the frontend why connecting to Discord failed.
+fn emit_client_error(handle: &AppHandle, app_id: Option<&str>, error: &rpc::Error) {
+    let error_payload = serde_json::json!({
+        "app_id": app_id,
+        "kind": error.kind(),
+        "reason": error.to_string(),
+    });
 
-        let output = std::process::Command::new("pkill")
-            .arg("-x")
-            .arg(&process_name)
-            .output()
-            .map_err(|e| format!("Failed to execute pkill: {}", e))?;
+    handle
+        .emit("client_error", error_payload)
+        .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
+}
 
-        if output.status.success() || output.status.code() == Some(1) {
-            Ok(())
-        } else {
-            Err(format!(
-                "Failed to stop process: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ))
-        }
-    }
-    #[cfg(target_os = "linux")]
-    {
-        let output = std::process::Command::new("pkill")
-            .arg("-f")
-            .arg(&exec_name)
-            .output()
-            .map_err(|e| format!("Failed to execute pkill: {}", e))?;
-
-        if output.status.success() || output.status.code() == Some(1) {
-            // pkill returns 1 if no processes were killed, which is fine
-            Ok(())
-        } else {
-            Err(format!(
-                "Failed to stop process: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ))
-        }
+/// Forwards reconnect progress of a pooled client to the frontend.
+fn forward_connection_events(
+    handle: &AppHandle,
+    app_id: String,
+) -> impl Fn(rpc::ConnectionEvent) + Send + Sync + 'static {
+    let handle = handle.clone();
+    move |event| {
+        let (event_name, payload) = match event {
+            rpc::ConnectionEvent::Reconnecting { reason } => (
+                "client_reconnecting",
+                serde_json::json!({
+                    "app_id": app_id,
+                    "reason": reason,
+                }),
+            ),
+            rpc::ConnectionEvent::Connected => (
+                "client_connected",
+                serde_json::json!({
+                    "app_id": app_id,
+                    "reconnected": true,
+                }),
+            ),
+        };
+        handle
+            .emit(event_name, payload)
+            .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
     }
 }
 
-/// Usage: Calling from JS:
-/// ```javascript
-/// await invoke('connect_to_discord_rpc_3', json, 'connect' | 'disconnect');
-#[tauri::command(rename_all = "snake_case")]
-fn connect_to_discord_rpc_3(handle: AppHandle, activity_json: String, action: String) {
-    let app = handle.clone();
-
-    let event_connecting = "client_connecting";
-    let event_connected = "client_connected";
-    let event_disconnect = "event_disconnect";
-    let event_connect = "event_connect";
-
-    let activity = runner::parse_activity_json(&activity_json).unwrap();
+/// Connects a Discord client for the app in `activity_json`, sets its activity and adds it to
+/// the pool. Failures are also reported to the frontend through `client_error`.
+async fn connect_discord_client(
+    handle: &AppHandle,
+    activity_json: String,
+    connect_timeout: Duration,
+) -> Result<i64, rpc::Error> {
+    let activity = runner::parse_activity_json(&activity_json).inspect_err(|e| {
+        emit_client_error(handle, None, e);
+    })?;
 
     let connecting_payload = serde_json::json!({
         "app_id": activity.app_id,
     });
+    handle
+        .emit("client_connecting", connecting_payload)
+        .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
 
-    let client_option = {
-        let mut client_guard = get_discord_client().lock().unwrap();
-        // Take the client out, leaving None in its place
-        client_guard.take()
-        // MutexGuard is dropped here at the end of scope
-    };
+    let on_connection = forward_connection_events(handle, activity.app_id.clone());
+    let app_id = get_presence_manager()
+        .connect(activity_json, connect_timeout, on_connection)
+        .await
+        .inspect_err(|e| emit_client_error(handle, Some(&activity.app_id), e))?;
 
-    let task = tauri::async_runtime::spawn(async move {
-        handle
-            .emit(event_connecting, connecting_payload)
-            .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
+    let connected_payload = serde_json::json!({
+        "app_id": activity.app_id,
+    });
+    handle
+        .emit("client_connected", connected_payload)
+        .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
 
-        let client = runner::set_activity(activity_json)
-            .await
-            .map_err(|e| {
-                println!("Failed to set activity: {}", e);
-            })
-            .unwrap();
+    Ok(app_id)
+}
 
-        let connected_payload = serde_json::json!({
-            "app_id": activity.app_id,
-        });
+fn connect_timeout(connect_timeout_secs: Option<u64>) -> Duration {
+    connect_timeout_secs
+        .map(Duration::from_secs)
+        .unwrap_or(rpc::DEFAULT_CONNECT_TIMEOUT)
+}
 
-        {
-            let mut client_guard = get_discord_client().lock().unwrap();
-            *client_guard = Some(client);
+/// Compatibility wrapper over `rpc_connect` and `rpc_disconnect` for older frontends.
+/// Connection errors are reported through the `client_error` event.
+///
+/// Usage: Calling from JS:
+/// ```javascript
+/// await invoke('connect_to_discord_rpc_3', { activity_json, action: 'connect' | 'disconnect' });
+/// ```
+#[tauri::command(rename_all = "snake_case")]
+fn connect_to_discord_rpc_3(
+    handle: AppHandle,
+    activity_json: String,
+    action: String,
+    connect_timeout_secs: Option<u64>,
+) {
+    let generation = LEGACY_DISCONNECT_GENERATION.load(Ordering::SeqCst);
+
+    tauri::async_runtime::spawn(async move {
+        if action == "disconnect" {
+            let app_id = runner::parse_activity_json(&activity_json)
+                .and_then(|activity| engine::parse_app_id(&activity.app_id));
+            let disconnected = match app_id {
+                Ok(app_id) => get_presence_manager().disconnect(app_id).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = disconnected {
+                emit_client_error(&handle, None, &e);
+            }
+            return;
         }
 
-        handle
-            .emit(event_connected, connected_payload)
-            .unwrap_or_else(|e| {
-                eprintln!("Failed to emit event: {}", e);
-            });
-
-        handle.listen(event_disconnect, move |_| {
-            println!("Disconnecting from Discord RPC inner");
-            let disconnect_task = tauri::async_runtime::spawn(async move {
-                let client_option = {
-                    let mut client_guard = get_discord_client().lock().unwrap();
-                    // Take the client out, leaving None in its place
-                    client_guard.take()
-                    // MutexGuard is dropped here at the end of scope
-                };
-                if let Some(client) = client_option {
-                    client.discord.disconnect().await;
-                    println!("Disconnected from Discord RPC inner");
+        let connected = connect_discord_client(
+            &handle,
+            activity_json,
+            connect_timeout(connect_timeout_secs),
+        )
+        .await;
+
+        if let Ok(app_id) = connected {
+            if LEGACY_DISCONNECT_GENERATION.load(Ordering::SeqCst) != generation {
+                println!("Disconnect requested while connecting, dropping client");
+                if let Err(e) = get_presence_manager().disconnect(app_id).await {
+                    emit_client_error(&handle, Some(&app_id.to_string()), &e);
                 }
-            });
-            // disconnect_task.abort();
-        });
+            }
+        }
     });
+}
 
-    app.listen(event_disconnect, move |_| {
-        println!("Disconnecting from Discord RPC...");
-        task.abort();
-    });
+/// Connects a Discord client for the app in `activity_json`. A client already connected for the
+/// same app_id is kept and only its activity changes, ignoring `connect_timeout_secs`. Clients
+/// for other apps stay connected.
+#[tauri::command(rename_all = "snake_case")]
+async fn rpc_connect(
+    handle: AppHandle,
+    activity_json: String,
+    connect_timeout_secs: Option<u64>,
+) -> Result<engine::ClientStatus, rpc::Error> {
+    let app_id = connect_discord_client(
+        &handle,
+        activity_json,
+        connect_timeout(connect_timeout_secs),
+    )
+    .await?;
+
+    Ok(get_presence_manager().status(app_id))
+}
+
+/// Sets the activity in `activity_json` on the client already connected for its app_id,
+/// reusing the connection instead of reconnecting.
+#[tauri::command(rename_all = "snake_case")]
+async fn rpc_update_activity(activity_json: String) -> Result<(), rpc::Error> {
+    get_presence_manager().update_activity(activity_json).await
+}
+
+/// Blanks the presence of the client connected for `app_id` while keeping it connected.
+#[tauri::command(rename_all = "snake_case")]
+async fn rpc_clear_activity(app_id: String) -> Result<(), rpc::Error> {
+    let app_id = engine::parse_app_id(&app_id)?;
+
+    get_presence_manager().clear_activity(app_id).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+async fn rpc_disconnect(app_id: String) -> Result<(), rpc::Error> {
+    let app_id = engine::parse_app_id(&app_id)?;
+
+    get_presence_manager().disconnect(app_id).await?;
+    Ok(())
 }
 
+/// Reports the connection state, user and current activity of the client for `app_id`.
 #[tauri::command(rename_all = "snake_case")]
-async fn fetch_gamelist_gh_mirror() -> tauri::ipc::Response {
-    let res = tauri_plugin_http::reqwest::get("https://markterence.github.io/discord-quest-completer/detectable.json").await;
-    tauri::ipc::Response::new(res.unwrap().text().await.unwrap())
+fn rpc_status(app_id: String) -> Result<engine::ClientStatus, rpc::Error> {
+    let app_id = engine::parse_app_id(&app_id)?;
+
+    Ok(get_presence_manager().status(app_id))
 }
 
 #[tauri::command(rename_all = "snake_case")]
-async fn fetch_gamelist_from_discord() -> tauri::ipc::Response {
-    let res = tauri_plugin_http::reqwest::get("https://discord.com/api/applications/detectable").await;
-    tauri::ipc::Response::new(res.unwrap().text().await.unwrap())
+fn rpc_list_connections() -> Vec<engine::ClientStatus> {
+    get_presence_manager().list()
+}
+
+// Embeds the frontend, so the app and the CLI share one copy.
+fn context() -> tauri::Context {
+    tauri::generate_context!()
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -441,15 +461,70 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_opener::init())
+        .setup(|app| {
+            // Older frontends disconnect everything through this event.
+            let handle = app.handle().clone();
+            app.handle().listen("event_disconnect", move |_| {
+                println!("Disconnecting from Discord RPC...");
+                LEGACY_DISCONNECT_GENERATION.fetch_add(1, Ordering::SeqCst);
+                let handle = handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    if let Err(e) = get_presence_manager().disconnect_all().await {
+                        emit_client_error(&handle, None, &e);
+                    }
+                });
+            });
+
+            app_dirs::init(app_dirs::AppDirs::from_handle(app.handle())?);
+            settings::restore();
+            queue::restore();
+            queue::spawn_worker(app.handle().clone());
+
+            // Runners left from an older version get upgraded, except those an earlier session
+            // or the CLI left running.
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = games::upgrade_runners().await {
+                    eprintln!("Failed to upgrade installed runners: {}", e);
+                }
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             create_fake_game,
             stop_process,
             connect_to_discord_rpc_3,
+            rpc_connect,
+            rpc_update_activity,
+            rpc_clear_activity,
+            rpc_disconnect,
+            rpc_status,
+            rpc_list_connections,
             run_background_process,
-            fetch_gamelist_gh_mirror,
-            fetch_gamelist_from_discord
+            gamelist::fetch_gamelist,
+            gamelist::get_gamelist_cache_info,
+            gamelist::get_gamelist_changes,
+            gamelist::get_gamelist_sources,
+            gamelist::set_gamelist_sources,
+            search::search_games,
+            settings::get_app_settings,
+            settings::set_install_strategy,
+            settings::set_unsigned_gamelists,
+            games::get_games_root,
+            games::set_games_root,
+            games::migrate_games_root,
+            games::list_installed_games,
+            games::uninstall_fake_game,
+            games::gc_games,
+            games::upgrade_runners,
+            queue::queue_submit,
+            queue::queue_status,
+            queue::queue_pause,
+            queue::queue_resume,
+            queue::queue_skip,
+            queue::queue_reorder,
+            queue::queue_cancel
         ])
-        .run(tauri::generate_context!())
+        .run(context())
         .expect("error while running tauri application");
 }
\ No newline at end of file
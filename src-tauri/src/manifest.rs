@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Written into every `<games_root>/<app_id>/` folder.
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+// Launches kept in the history, older ones are dropped.
+const MAX_LAUNCH_HISTORY: usize = 50;
+
+// Installs, launches and runner exits all update manifests from different threads.
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// What was installed for a game and when it was played.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameManifest {
+    pub app_id: i64,
+    pub name: String,
+    pub created: u64,
+    pub executables: Vec<ManifestExecutable>,
+    #[serde(default)]
+    pub launches: Vec<LaunchRecord>,
+}
+
+/// An executable entry from the detectable list and the runner installed for it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ManifestExecutable {
+    /// The detectable list's name, relative to the game's folder and `/` separated.
+    pub name: String,
+    pub os: String,
+    pub is_launcher: bool,
+    pub template_version: String,
+    pub template_hash: String,
+    pub installed: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LaunchRecord {
+    pub executable: String,
+    pub pid: Option<u32>,
+    pub started: u64,
+    pub ended: Option<u64>,
+    pub exit_code: Option<i32>,
+}
+
+impl GameManifest {
+    pub fn executable(&self, name: &str) -> Option<&ManifestExecutable> {
+        self.executables
+            .iter()
+            .find(|executable| executable.name == name)
+    }
+
+    /// When any of the game's executables was last launched.
+    pub fn last_launched(&self) -> Option<u64> {
+        self.launches.iter().map(|launch| launch.started).max()
+    }
+
+    pub fn last_launched_executable(&self, name: &str) -> Option<u64> {
+        self.launches
+            .iter()
+            .filter(|launch| launch.executable == name)
+            .map(|launch| launch.started)
+            .max()
+    }
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+pub fn file_sha256(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+    Ok(format!("{:x}", Sha256::digest(bytes)))
+}
+
+fn manifest_path(game_dir: &Path) -> PathBuf {
+    game_dir.join(MANIFEST_FILE_NAME)
+}
+
+/// Reads the manifest of the game installed in `game_dir`, if it has one.
+pub fn read(game_dir: &Path) -> Option<GameManifest> {
+    let json = fs::read_to_string(manifest_path(game_dir)).ok()?;
+    serde_json::from_str(&json)
+        .map_err(|e| eprintln!("Failed to parse manifest in {:?}: {}", game_dir, e))
+        .ok()
+}
+
+fn write(game_dir: &Path, manifest: &GameManifest) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(manifest)
+        .map_err(|e| format!("Failed to serialize manifest: {}", e))?;
+
+    // Written next to the manifest and renamed over it, so a crash never leaves half a file.
+    let temp_path = game_dir.join(format!(".{}.tmp", MANIFEST_FILE_NAME));
+    fs::write(&temp_path, json).map_err(|e| format!("Failed to write manifest: {}", e))?;
+    fs::rename(&temp_path, manifest_path(game_dir))
+        .map_err(|e| format!("Failed to write manifest: {}", e))
+}
+
+/// Reads, changes and writes back the manifest in `game_dir`. Games installed before manifests
+/// existed have none, `change` gets `None` for those.
+fn update(
+    game_dir: &Path,
+    change: impl FnOnce(Option<GameManifest>) -> Option<GameManifest>,
+) -> Result<(), String> {
+    let _guard = MANIFEST_LOCK.lock().unwrap();
+    match change(read(game_dir)) {
+        Some(manifest) => write(game_dir, &manifest),
+        None => Ok(()),
+    }
+}
+
+/// Records an installed executable, replacing an earlier install under the same name.
+pub fn record_install(
+    game_dir: &Path,
+    app_id: i64,
+    name: &str,
+    executable: ManifestExecutable,
+) -> Result<(), String> {
+    update(game_dir, |manifest| {
+        let mut manifest = manifest.unwrap_or_else(|| GameManifest {
+            app_id,
+            name: name.to_string(),
+            created: executable.installed,
+            executables: Vec::new(),
+            launches: Vec::new(),
+        });
+        if !name.is_empty() {
+            manifest.name = name.to_string();
+        }
+        manifest
+            .executables
+            .retain(|installed| installed.name != executable.name);
+        manifest.executables.push(executable);
+        Some(manifest)
+    })
+}
+
+/// Drops an uninstalled executable from the manifest, and the manifest itself once nothing is
+/// left installed so the game's folder can be removed.
+pub fn record_uninstall(game_dir: &Path, executable_name: &str) -> Result<(), String> {
+    let _guard = MANIFEST_LOCK.lock().unwrap();
+    let Some(mut manifest) = read(game_dir) else {
+        return Ok(());
+    };
+
+    manifest
+        .executables
+        .retain(|installed| installed.name != executable_name);
+    if manifest.executables.is_empty() {
+        return fs::remove_file(manifest_path(game_dir))
+            .map_err(|e| format!("Failed to remove manifest: {}", e));
+    }
+    write(game_dir, &manifest)
+}
+
+/// Records that an installed executable's runner was replaced with a newer template.
+pub fn record_upgrade(
+    game_dir: &Path,
+    executable_name: &str,
+    template_version: &str,
+    template_hash: &str,
+) -> Result<(), String> {
+    update(game_dir, |manifest| {
+        let mut manifest = manifest?;
+        let executable = manifest
+            .executables
+            .iter_mut()
+            .find(|installed| installed.name == executable_name)?;
+        executable.template_version = template_version.to_string();
+        executable.template_hash = template_hash.to_string();
+        executable.installed = now_secs();
+        Some(manifest)
+    })
+}
+
+pub fn record_launch(game_dir: &Path, executable: &str, pid: Option<u32>) -> Result<(), String> {
+    update(game_dir, |manifest| {
+        let mut manifest = manifest?;
+        manifest.launches.push(LaunchRecord {
+            executable: executable.to_string(),
+            pid,
+            started: now_secs(),
+            ended: None,
+            exit_code: None,
+        });
+        let overflow = manifest.launches.len().saturating_sub(MAX_LAUNCH_HISTORY);
+        manifest.launches.drain(..overflow);
+        Some(manifest)
+    })
+}
+
+pub fn record_exit(game_dir: &Path, pid: u32, exit_code: Option<i32>) -> Result<(), String> {
+    update(game_dir, |manifest| {
+        let mut manifest = manifest?;
+        let launch = manifest
+            .launches
+            .iter_mut()
+            .rev()
+            .find(|launch| launch.pid == Some(pid) && launch.ended.is_none())?;
+        launch.ended = Some(now_secs());
+        launch.exit_code = exit_code;
+        Some(manifest)
+    })
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+use std::path::PathBuf;
+
+// Device names Windows reserves in every directory, with or without an extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Why a path from the frontend or the detectable list was rejected.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PathError {
+    /// The executable name is empty.
+    Empty,
+    /// The path starts at the filesystem root, or is a UNC path.
+    Absolute(String),
+    /// The path has a Windows drive prefix such as `C:`.
+    DrivePrefix(String),
+    /// A component is `..`.
+    ParentDir(String),
+    /// The path contains a NUL byte.
+    NulByte,
+    /// A component uses a character that is not allowed in file names.
+    InvalidCharacter(char),
+    /// A component is a reserved device name such as `CON` or `LPT1`.
+    ReservedName(String),
+    /// The executable name has directory components.
+    NotAFileName(String),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::Empty => write!(f, "Executable name is empty"),
+            PathError::Absolute(path) => write!(f, "Path {:?} must be relative", path),
+            PathError::DrivePrefix(path) => write!(f, "Path {:?} has a drive prefix", path),
+            PathError::ParentDir(path) => write!(f, "Path {:?} escapes the game folder", path),
+            PathError::NulByte => write!(f, "Path contains a NUL byte"),
+            PathError::InvalidCharacter(c) => write!(f, "Path contains invalid character {:?}", c),
+            PathError::ReservedName(name) => write!(f, "{:?} is a reserved file name", name),
+            PathError::NotAFileName(name) => {
+                write!(f, "Executable name {:?} must not contain directories", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+fn is_separator(c: char) -> bool {
+    c == '/' || c == '\\'
+}
+
+fn has_drive_prefix(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':'
+}
+
+fn is_reserved_name(component: &str) -> bool {
+    // Windows ignores the extension and trailing dots or spaces, so `con.txt` and `NUL .` are
+    // the devices too.
+    let stem = component.split('.').next().unwrap_or(component);
+    let stem = stem.trim_end_matches([' ', '.']);
+    RESERVED_NAMES
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(stem))
+}
+
+fn check_component(component: &str, path: &str) -> Result<(), PathError> {
+    if component == ".." {
+        return Err(PathError::ParentDir(path.to_string()));
+    }
+    if let Some(c) = component.chars().find(|c| c.is_control() || *c == ':') {
+        return Err(PathError::InvalidCharacter(c));
+    }
+    if is_reserved_name(component) {
+        return Err(PathError::ReservedName(component.to_string()));
+    }
+    Ok(())
+}
+
+fn check_whole_path(path: &str) -> Result<(), PathError> {
+    if path.contains('\0') {
+        return Err(PathError::NulByte);
+    }
+    // Checked on every platform, a list entry written for Windows must not escape on Linux either.
+    if has_drive_prefix(path) {
+        return Err(PathError::DrivePrefix(path.to_string()));
+    }
+    if path.starts_with(is_separator) {
+        return Err(PathError::Absolute(path.to_string()));
+    }
+    Ok(())
+}
+
+/// Normalizes the folder of a detectable executable, relative to the game's folder.
+///
+/// Both `/` and `\` are separators, empty and `.` components are dropped. An empty path is the
+/// game folder itself.
+pub fn relative_game_path(path: &str) -> Result<PathBuf, PathError> {
+    check_whole_path(path)?;
+
+    let mut relative = PathBuf::new();
+    for component in path.split(is_separator) {
+        if component.is_empty() || component == "." {
+            continue;
+        }
+        check_component(component, path)?;
+        relative.push(component);
+    }
+
+    Ok(relative)
+}
+
+/// Checks that `name` is a single file name that stays inside the folder it is joined onto.
+pub fn executable_file_name(name: &str) -> Result<&str, PathError> {
+    check_whole_path(name)?;
+
+    if name.is_empty() {
+        return Err(PathError::Empty);
+    }
+    if name.contains(is_separator) {
+        return Err(PathError::NotAFileName(name.to_string()));
+    }
+    if name == "." {
+        return Err(PathError::NotAFileName(name.to_string()));
+    }
+    check_component(name, name)?;
+
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn accepts_nested_relative_paths() {
+        assert_eq!(
+            relative_game_path("bin/win64").unwrap(),
+            Path::new("bin").join("win64")
+        );
+        assert_eq!(
+            relative_game_path("_retail_\\Data").unwrap(),
+            Path::new("_retail_").join("Data")
+        );
+        assert_eq!(relative_game_path("").unwrap(), PathBuf::new());
+    }
+
+    #[test]
+    fn drops_empty_and_current_dir_components() {
+        assert_eq!(
+            relative_game_path("./bin//./x64/").unwrap(),
+            Path::new("bin").join("x64")
+        );
+    }
+
+    #[test]
+    fn rejects_parent_dir_components() {
+        for path in ["..", "../..", "bin/../../etc", "bin\\..\\..", "a/./../b"] {
+            assert_eq!(
+                relative_game_path(path),
+                Err(PathError::ParentDir(path.to_string())),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_dots_inside_names() {
+        assert_eq!(relative_game_path("...").unwrap(), Path::new("..."));
+        assert_eq!(relative_game_path("v1..2").unwrap(), Path::new("v1..2"));
+        assert_eq!(executable_file_name("game..exe"), Ok("game..exe"));
+    }
+
+    #[test]
+    fn rejects_absolute_paths() {
+        for path in [
+            "/etc",
+            "/",
+            "\\Windows\\System32",
+            "\\\\server\\share",
+            "//server",
+        ] {
+            assert_eq!(
+                relative_game_path(path),
+                Err(PathError::Absolute(path.to_string())),
+                "{}",
+                path
+            );
+        }
+        assert_eq!(
+            executable_file_name("/usr/bin/game"),
+            Err(PathError::Absolute("/usr/bin/game".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_drive_prefixes() {
+        for path in ["C:\\Windows", "c:/users", "D:", "z:relative"] {
+            assert_eq!(
+                relative_game_path(path),
+                Err(PathError::DrivePrefix(path.to_string())),
+                "{}",
+                path
+            );
+        }
+        assert_eq!(
+            executable_file_name("C:game.exe"),
+            Err(PathError::DrivePrefix("C:game.exe".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_nul_bytes_and_control_characters() {
+        assert_eq!(relative_game_path("bin\0/x"), Err(PathError::NulByte));
+        assert_eq!(
+            executable_file_name("game.exe\0.txt"),
+            Err(PathError::NulByte)
+        );
+        assert_eq!(
+            relative_game_path("bin\n"),
+            Err(PathError::InvalidCharacter('\n'))
+        );
+    }
+
+    #[test]
+    fn rejects_alternate_data_streams() {
+        assert_eq!(
+            executable_file_name("game.exe:stream"),
+            Err(PathError::InvalidCharacter(':'))
+        );
+        assert_eq!(
+            relative_game_path("bin/data:x"),
+            Err(PathError::InvalidCharacter(':'))
+        );
+    }
+
+    #[test]
+    fn rejects_reserved_names() {
+        for name in [
+            "CON", "con", "nul.exe", "Com1", "LPT9.txt", "aux .dll", "PRN.",
+        ] {
+            assert_eq!(
+                executable_file_name(name),
+                Err(PathError::ReservedName(name.to_string())),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(
+            relative_game_path("bin/CON/x"),
+            Err(PathError::ReservedName("CON".to_string()))
+        );
+        assert_eq!(executable_file_name("console.exe"), Ok("console.exe"));
+        assert_eq!(executable_file_name("COM10.exe"), Ok("COM10.exe"));
+    }
+
+    #[test]
+    fn executable_name_must_be_a_single_component() {
+        for name in ["bin/game.exe", "bin\\game.exe", "game.exe/"] {
+            assert_eq!(
+                executable_file_name(name),
+                Err(PathError::NotAFileName(name.to_string())),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(executable_file_name(""), Err(PathError::Empty));
+        assert_eq!(
+            executable_file_name("."),
+            Err(PathError::NotAFileName(".".to_string()))
+        );
+        assert_eq!(
+            executable_file_name(".."),
+            Err(PathError::ParentDir("..".to_string()))
+        );
+    }
+
+    #[test]
+    fn accepts_plain_executable_names() {
+        for name in [
+            "game.exe",
+            "Game Launcher.exe",
+            "hl2_linux",
+            "Some Game.app",
+            "ゲーム.exe",
+        ] {
+            assert_eq!(executable_file_name(name), Ok(name));
+        }
+    }
+}
@@ -0,0 +1,583 @@
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::app_dirs;
+use crate::paths;
+
+// How often the queue is written to disk while an entry is running.
+const PERSIST_EVERY_SECS: u64 = 10;
+
+/// A game submitted to the queue from the frontend.
+#[derive(Deserialize)]
+pub struct QueueEntryRequest {
+    pub app_id: i64,
+    pub name: String,
+    pub path: String,
+    pub executable_name: String,
+    pub duration_secs: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct QueueEntry {
+    pub id: u64,
+    pub app_id: i64,
+    pub name: String,
+    pub path: String,
+    pub executable_name: String,
+    pub duration_secs: u64,
+    /// Time already played, kept across pauses and app restarts.
+    pub elapsed_secs: u64,
+}
+
+/// The runner of the entry at the front of the queue.
+#[derive(Clone, Copy)]
+struct RunningEntry {
+    id: u64,
+    pid: Option<u32>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+pub struct QueueState {
+    /// Entries waiting to be played. The first one is the one currently playing, if any.
+    pub entries: Vec<QueueEntry>,
+    pub paused: bool,
+    #[serde(default)]
+    next_id: u64,
+    #[serde(skip)]
+    running: Option<RunningEntry>,
+}
+
+impl QueueState {
+    fn snapshot(&self) -> serde_json::Value {
+        serde_json::json!({
+            "entries": self.entries,
+            "paused": self.paused,
+            "running_id": self.running.map(|running| running.id),
+        })
+    }
+
+    /// Appends `requests` to the queue. Nothing is added unless every one of them is valid,
+    /// with the same path checks as installing the game.
+    fn submit(&mut self, requests: Vec<QueueEntryRequest>) -> Result<(), String> {
+        for request in &requests {
+            if request.duration_secs == 0 {
+                return Err(format!(
+                    "Duration for {} must be greater than 0",
+                    request.name
+                ));
+            }
+            paths::relative_game_path(&request.path)
+                .and(paths::executable_file_name(&request.executable_name))
+                .map_err(|e| format!("Invalid executable for {}: {}", request.name, e))?;
+        }
+
+        for request in requests {
+            self.next_id += 1;
+            self.entries.push(QueueEntry {
+                id: self.next_id,
+                app_id: request.app_id,
+                name: request.name,
+                path: request.path,
+                executable_name: request.executable_name,
+                duration_secs: request.duration_secs,
+                elapsed_secs: 0,
+            });
+        }
+        Ok(())
+    }
+
+    /// Removes the entry at the front of the queue, forgetting its runner.
+    fn pop_front(&mut self) -> Option<QueueEntry> {
+        if self.entries.is_empty() {
+            return None;
+        }
+        self.running = None;
+        Some(self.entries.remove(0))
+    }
+
+    /// Puts the entries listed in `ids` first, see `queue_reorder`.
+    fn reorder(&mut self, ids: Vec<u64>) -> Result<(), String> {
+        if let Some(id) = ids
+            .iter()
+            .find(|id| !self.entries.iter().any(|entry| entry.id == **id))
+        {
+            return Err(format!("Unknown queue entry: {}", id));
+        }
+
+        let running_id = self.running.map(|running| running.id);
+        let mut remaining = std::mem::take(&mut self.entries);
+        let mut reordered = Vec::with_capacity(remaining.len());
+
+        for id in running_id.into_iter().chain(ids) {
+            if let Some(index) = remaining.iter().position(|entry| entry.id == id) {
+                reordered.push(remaining.remove(index));
+            }
+        }
+        reordered.append(&mut remaining);
+        self.entries = reordered;
+        Ok(())
+    }
+}
+
+static QUEUE: OnceCell<Mutex<QueueState>> = OnceCell::new();
+
+fn get_queue() -> &'static Mutex<QueueState> {
+    QUEUE.get_or_init(|| Mutex::new(QueueState::default()))
+}
+
+fn parse_saved(json: &str) -> Result<QueueState, String> {
+    serde_json::from_str(json).map_err(|e| format!("Failed to parse queue file: {}", e))
+}
+
+fn queue_file_path() -> Result<PathBuf, String> {
+    Ok(app_dirs::get()?.data_dir.join("queue.json"))
+}
+
+fn persist(state: &QueueState) {
+    let result = queue_file_path().and_then(|file_path| {
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(state)
+            .map_err(|e| format!("Failed to serialize queue: {}", e))?;
+        fs::write(&file_path, json).map_err(|e| format!("Failed to write queue file: {}", e))
+    });
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+fn emit(handle: &AppHandle, event: &str, payload: serde_json::Value) {
+    handle
+        .emit(event, payload)
+        .unwrap_or_else(|e| eprintln!("Failed to emit event: {}", e));
+}
+
+fn emit_updated(handle: &AppHandle, state: &QueueState) {
+    emit(handle, "queue_updated", state.snapshot());
+}
+
+/// Loads the queue saved by a previous run. An entry that was playing when the app closed
+/// keeps its progress and is started again by the worker.
+pub fn restore() {
+    let saved = queue_file_path().and_then(|file_path| {
+        if !file_path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&file_path)
+            .map_err(|e| format!("Failed to read queue file: {}", e))?;
+        parse_saved(&json).map(Some)
+    });
+
+    match saved {
+        Ok(Some(saved)) => {
+            println!("Restored quest queue with {} entries", saved.entries.len());
+            *get_queue().lock().unwrap() = saved;
+        }
+        Ok(None) => {}
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+/// Starts the background task that plays the queue one entry at a time.
+pub fn spawn_worker(handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            ticker.tick().await;
+            tick(&handle).await;
+        }
+    });
+}
+
+async fn tick(handle: &AppHandle) {
+    let (entry, running) = {
+        let state = get_queue().lock().unwrap();
+        if state.paused {
+            return;
+        }
+        match state.entries.first() {
+            Some(entry) => (entry.clone(), state.running),
+            None => return,
+        }
+    };
+
+    match running {
+        None => start_entry(handle, entry).await,
+        Some(running) => advance_entry(handle, entry, running).await,
+    }
+}
+
+async fn start_entry(handle: &AppHandle, entry: QueueEntry) {
+    // Installing copies and hashes the runner template, which would hold up the async runtime.
+    let launched = {
+        let handle = handle.clone();
+        let entry = entry.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            crate::installer()
+                .and_then(|installer| {
+                    installer.install(
+                        crate::get_runner_manager(),
+                        &entry.path,
+                        &entry.executable_name,
+                        entry.app_id,
+                        Some(entry.name.clone()),
+                        None,
+                    )
+                })
+                .and_then(|_| {
+                    crate::launch_runner(
+                        &handle,
+                        &entry.name,
+                        &entry.path,
+                        &entry.executable_name,
+                        entry.app_id,
+                    )
+                })
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("Failed to start runner: {}", e)))
+    };
+
+    let mut state = get_queue().lock().unwrap();
+    // The queue may have been paused or changed while the runner was being installed.
+    if state.paused || state.entries.first().map(|first| first.id) != Some(entry.id) {
+        if let Ok(pid) = launched {
+            let _ = crate::get_runner_manager().stop_pid(pid, &entry.executable_name);
+        }
+        return;
+    }
+
+    match launched {
+        Ok(pid) => {
+            state.running = Some(RunningEntry { id: entry.id, pid });
+            println!("Queue started {} (app_id {})", entry.name, entry.app_id);
+            emit(handle, "queue_entry_started", serde_json::json!(entry));
+        }
+        Err(error) => {
+            state.entries.remove(0);
+            eprintln!("Queue failed to start {}: {}", entry.name, error);
+            emit(
+                handle,
+                "queue_entry_failed",
+                serde_json::json!({ "entry": entry, "error": error }),
+            );
+        }
+    }
+
+    persist(&state);
+    emit_updated(handle, &state);
+}
+
+async fn advance_entry(handle: &AppHandle, entry: QueueEntry, running: RunningEntry) {
+    let runner_alive = match running.pid {
+        Some(pid) => crate::get_runner_manager().is_active(pid),
+        None => true,
+    };
+
+    let elapsed_secs = {
+        let mut state = get_queue().lock().unwrap();
+        if state.running.map(|running| running.id) != Some(entry.id) {
+            return;
+        }
+        let Some(front) = state.entries.first_mut() else {
+            return;
+        };
+
+        if !runner_alive {
+            let entry = state.entries.remove(0);
+            state.running = None;
+            emit(
+                handle,
+                "queue_entry_failed",
+                serde_json::json!({
+                    "entry": entry,
+                    "error": "Runner exited before its duration was up",
+                }),
+            );
+            persist(&state);
+            emit_updated(handle, &state);
+            return;
+        }
+
+        front.elapsed_secs += 1;
+        let elapsed_secs = front.elapsed_secs;
+        if elapsed_secs % PERSIST_EVERY_SECS == 0 {
+            persist(&state);
+        }
+        elapsed_secs
+    };
+
+    if elapsed_secs < entry.duration_secs {
+        emit(
+            handle,
+            "queue_entry_progress",
+            serde_json::json!({
+                "id": entry.id,
+                "app_id": entry.app_id,
+                "elapsed_secs": elapsed_secs,
+                "remaining_secs": entry.duration_secs - elapsed_secs,
+            }),
+        );
+        return;
+    }
+
+    let stopped = crate::get_runner_manager().stop_pid(running.pid, &entry.executable_name);
+    if let Err(e) = crate::get_presence_manager()
+        .end_session(entry.app_id)
+        .await
+    {
+        eprintln!("{}", e);
+    }
+
+    let mut state = get_queue().lock().unwrap();
+    if state.running.map(|running| running.id) == Some(entry.id) {
+        let entry = state.entries.remove(0);
+        state.running = None;
+        println!("Queue completed {} (app_id {})", entry.name, entry.app_id);
+        emit(
+            handle,
+            "queue_entry_completed",
+            serde_json::json!({ "entry": entry, "error": stopped.err() }),
+        );
+        if state.entries.is_empty() {
+            emit(handle, "queue_finished", serde_json::json!({}));
+        }
+    }
+
+    persist(&state);
+    emit_updated(handle, &state);
+}
+
+/// Stops the runner of the entry at the front of the queue, keeping its progress.
+fn stop_running(state: &mut QueueState) -> Result<(), String> {
+    let Some(running) = state.running.take() else {
+        return Ok(());
+    };
+    let executable_name = state
+        .entries
+        .iter()
+        .find(|entry| entry.id == running.id)
+        .map(|entry| entry.executable_name.clone())
+        .unwrap_or_default();
+
+    crate::get_runner_manager().stop_pid(running.pid, &executable_name)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn queue_submit(
+    handle: AppHandle,
+    entries: Vec<QueueEntryRequest>,
+) -> Result<serde_json::Value, String> {
+    let mut state = get_queue().lock().unwrap();
+    state.submit(entries)?;
+
+    persist(&state);
+    emit_updated(&handle, &state);
+    Ok(state.snapshot())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn queue_status() -> serde_json::Value {
+    get_queue().lock().unwrap().snapshot()
+}
+
+/// Pauses the queue. The current runner is stopped and picks up where it left off on resume.
+#[tauri::command(rename_all = "snake_case")]
+pub fn queue_pause(handle: AppHandle) -> Result<serde_json::Value, String> {
+    let mut state = get_queue().lock().unwrap();
+    state.paused = true;
+    let stopped = stop_running(&mut state);
+
+    persist(&state);
+    emit(&handle, "queue_paused", state.snapshot());
+    emit_updated(&handle, &state);
+    stopped.map(|_| state.snapshot())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn queue_resume(handle: AppHandle) -> serde_json::Value {
+    let mut state = get_queue().lock().unwrap();
+    state.paused = false;
+
+    persist(&state);
+    emit(&handle, "queue_resumed", state.snapshot());
+    emit_updated(&handle, &state);
+    state.snapshot()
+}
+
+/// Drops the entry at the front of the queue, stopping it if it's playing.
+#[tauri::command(rename_all = "snake_case")]
+pub fn queue_skip(handle: AppHandle) -> Result<serde_json::Value, String> {
+    let mut state = get_queue().lock().unwrap();
+    let stopped = stop_running(&mut state);
+    let Some(entry) = state.pop_front() else {
+        return Err("Queue is empty".to_string());
+    };
+    emit(&handle, "queue_entry_skipped", serde_json::json!(entry));
+
+    persist(&state);
+    emit_updated(&handle, &state);
+    stopped.map(|_| state.snapshot())
+}
+
+/// Reorders the queue by entry id. Ids that aren't listed keep their relative order after the
+/// listed ones, and the entry that is currently playing always stays first.
+#[tauri::command(rename_all = "snake_case")]
+pub fn queue_reorder(handle: AppHandle, ids: Vec<u64>) -> Result<serde_json::Value, String> {
+    let mut state = get_queue().lock().unwrap();
+    state.reorder(ids)?;
+
+    persist(&state);
+    emit_updated(&handle, &state);
+    Ok(state.snapshot())
+}
+
+/// Stops the current runner and empties the queue.
+#[tauri::command(rename_all = "snake_case")]
+pub fn queue_cancel(handle: AppHandle) -> Result<serde_json::Value, String> {
+    let mut state = get_queue().lock().unwrap();
+    let stopped = stop_running(&mut state);
+    state.entries.clear();
+    state.paused = false;
+
+    persist(&state);
+    emit(&handle, "queue_cancelled", state.snapshot());
+    emit_updated(&handle, &state);
+    stopped.map(|_| state.snapshot())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(name: &str, duration_secs: u64) -> QueueEntryRequest {
+        QueueEntryRequest {
+            app_id: 100,
+            name: name.to_string(),
+            path: "bin".to_string(),
+            executable_name: "game.exe".to_string(),
+            duration_secs,
+        }
+    }
+
+    fn queue(names: &[&str]) -> QueueState {
+        let mut state = QueueState::default();
+        state
+            .submit(names.iter().map(|name| request(name, 60)).collect())
+            .unwrap();
+        state
+    }
+
+    fn ids(state: &QueueState) -> Vec<u64> {
+        state.entries.iter().map(|entry| entry.id).collect()
+    }
+
+    #[test]
+    fn submit_assigns_increasing_ids() {
+        let mut state = queue(&["a", "b"]);
+        state.submit(vec![request("c", 60)]).unwrap();
+        assert_eq!(ids(&state), [1, 2, 3]);
+        assert_eq!(state.entries[2].name, "c");
+        assert_eq!(state.entries[2].elapsed_secs, 0);
+    }
+
+    #[test]
+    fn submit_adds_nothing_when_an_entry_is_invalid() {
+        let mut state = queue(&["a"]);
+        let error = state
+            .submit(vec![request("b", 60), request("c", 0)])
+            .unwrap_err();
+        assert!(error.contains("c"), "{}", error);
+        assert_eq!(ids(&state), [1]);
+
+        // The ids of the rejected entries aren't used up either.
+        state.submit(vec![request("b", 60)]).unwrap();
+        assert_eq!(ids(&state), [1, 2]);
+    }
+
+    #[test]
+    fn submit_rejects_paths_outside_the_game_folder() {
+        let mut state = queue(&["a"]);
+        let escaping = QueueEntryRequest {
+            path: "../other".to_string(),
+            ..request("b", 60)
+        };
+        assert!(state.submit(vec![escaping]).is_err());
+
+        let nested = QueueEntryRequest {
+            executable_name: "bin/game.exe".to_string(),
+            ..request("c", 60)
+        };
+        let error = state.submit(vec![nested]).unwrap_err();
+        assert!(error.contains("c"), "{}", error);
+        assert_eq!(ids(&state), [1]);
+    }
+
+    #[test]
+    fn reorder_puts_listed_entries_first() {
+        let mut state = queue(&["a", "b", "c", "d"]);
+        state.reorder(vec![3, 1]).unwrap();
+        assert_eq!(ids(&state), [3, 1, 2, 4]);
+    }
+
+    #[test]
+    fn reorder_keeps_the_running_entry_first() {
+        let mut state = queue(&["a", "b", "c"]);
+        state.running = Some(RunningEntry { id: 1, pid: None });
+        state.reorder(vec![3, 2, 1]).unwrap();
+        assert_eq!(ids(&state), [1, 3, 2]);
+    }
+
+    #[test]
+    fn reorder_rejects_unknown_ids() {
+        let mut state = queue(&["a", "b"]);
+        assert!(state.reorder(vec![2, 7]).is_err());
+        assert_eq!(ids(&state), [1, 2]);
+    }
+
+    #[test]
+    fn pop_front_skips_the_running_entry() {
+        let mut state = queue(&["a", "b"]);
+        state.running = Some(RunningEntry { id: 1, pid: None });
+
+        let skipped = state.pop_front().unwrap();
+        assert_eq!(skipped.id, 1);
+        assert!(state.running.is_none());
+        assert_eq!(ids(&state), [2]);
+
+        state.pop_front().unwrap();
+        assert!(state.pop_front().is_none());
+    }
+
+    #[test]
+    fn restore_keeps_progress_but_not_the_runner() {
+        let mut state = queue(&["a", "b"]);
+        state.paused = true;
+        state.entries[0].elapsed_secs = 42;
+        state.running = Some(RunningEntry {
+            id: 1,
+            pid: Some(1234),
+        });
+
+        let saved = serde_json::to_string(&state).unwrap();
+        let restored = parse_saved(&saved).unwrap();
+        assert_eq!(ids(&restored), [1, 2]);
+        assert_eq!(restored.entries[0].elapsed_secs, 42);
+        assert!(restored.paused);
+        // The worker starts the front entry again.
+        assert!(restored.running.is_none());
+
+        // New ids carry on after the restored ones.
+        let mut restored = restored;
+        restored.submit(vec![request("c", 60)]).unwrap();
+        assert_eq!(ids(&restored), [1, 2, 3]);
+    }
+}
@@ -1,15 +1,259 @@
 pub use discord_sdk as ds;
 pub use tokio;
 
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long `make_client` waits for Discord to accept the IPC handshake.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Errors from connecting to Discord and building activities.
+#[derive(Debug)]
+pub enum Error {
+    /// The activity JSON sent by the frontend could not be parsed.
+    InvalidActivity(String),
+    /// An activity field is outside what Discord accepts.
+    InvalidActivityField { field: &'static str, reason: String },
+    /// The app_id is not a valid Discord application id.
+    InvalidAppId(String),
+    /// The Discord client could not be created.
+    CreateClient(ds::Error),
+    /// Discord refused the connection or went away while connecting.
+    Disconnected(String),
+    /// Discord did not answer within the connect timeout, usually because it isn't running.
+    ConnectTimeout(Duration),
+    /// Discord rejected the activity update.
+    UpdateActivity(ds::Error),
+    /// Discord rejected clearing the activity.
+    ClearActivity(ds::Error),
+    /// There is no client connected for the app_id.
+    NotConnected(ds::AppId),
+    /// The connection could not be closed because something else still uses it.
+    StillInUse(ds::AppId),
+}
+
+impl Error {
+    /// A stable identifier for the frontend to branch on.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::InvalidActivity(_) => "invalid_activity",
+            Error::InvalidActivityField { .. } => "invalid_activity_field",
+            Error::InvalidAppId(_) => "invalid_app_id",
+            Error::CreateClient(_) => "create_client",
+            Error::Disconnected(_) => "disconnected",
+            Error::ConnectTimeout(_) => "connect_timeout",
+            Error::UpdateActivity(_) => "update_activity",
+            Error::ClearActivity(_) => "clear_activity",
+            Error::NotConnected(_) => "not_connected",
+            Error::StillInUse(_) => "still_in_use",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidActivity(e) => write!(f, "Failed to parse activity JSON: {}", e),
+            Error::InvalidActivityField { field, reason } => {
+                write!(f, "Invalid activity field `{}`: {}", field, reason)
+            }
+            Error::InvalidAppId(e) => write!(f, "Failed to parse app_id: {}", e),
+            Error::CreateClient(e) => write!(f, "Unable to create Discord client: {}", e),
+            Error::Disconnected(e) => write!(f, "Failed to connect to Discord: {}", e),
+            Error::ConnectTimeout(timeout) => write!(
+                f,
+                "Timed out after {}s waiting for Discord, is it running?",
+                timeout.as_secs()
+            ),
+            Error::UpdateActivity(e) => write!(f, "Failed to update activity: {}", e),
+            Error::ClearActivity(e) => write!(f, "Failed to clear activity: {}", e),
+            Error::NotConnected(app_id) => {
+                write!(f, "No Discord client connected for app_id {}", app_id)
+            }
+            Error::StillInUse(app_id) => write!(
+                f,
+                "The Discord client for app_id {} is still in use and was not disconnected",
+                app_id
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// Commands return errors to the frontend in the same shape as the `client_error` event.
+impl serde::Serialize for Error {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut error = serializer.serialize_struct("Error", 2)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("reason", &self.to_string())?;
+        error.end()
+    }
+}
+
 /// Application identifier for "Andy's Test App" used in the Discord SDK's
 /// examples.
 pub struct Client {
-    pub discord: ds::Discord,
+    pub app_id: ds::AppId,
+    pub discord: Arc<ds::Discord>,
     pub wheel: ds::wheel::Wheel,
     pub user: ds::user::User,
+    /// The activity last accepted by Discord, re-applied after a reconnect.
+    last_activity: Arc<Mutex<Option<ds::activity::Activity>>>,
+    /// The task started by `watch_connection`, stopped before disconnecting.
+    watcher: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+/// Connection changes reported by `Client::watch_connection`.
+pub enum ConnectionEvent {
+    /// The connection dropped, discord-sdk keeps re-dialing until it is back.
+    Reconnecting {
+        reason: String,
+    },
+    Connected,
+}
+
+impl Client {
+    /// Whether the IPC connection is currently up, it is down while Discord restarts.
+    pub fn is_connected(&self) -> bool {
+        matches!(
+            *self.wheel.user().0.borrow(),
+            ds::wheel::UserState::Connected(_)
+        )
+    }
+
+    pub fn last_activity(&self) -> Option<ds::activity::Activity> {
+        self.last_activity.lock().unwrap().clone()
+    }
+
+    pub async fn update_activity(
+        &self,
+        activity: ds::activity::ActivityBuilder,
+    ) -> Result<(), Error> {
+        let args = ds::activity::ActivityArgs::from(activity);
+        if !self.is_connected() {
+            // The connection watcher applies it once Discord is back.
+            *self.last_activity.lock().unwrap() = args.activity;
+            return Ok(());
+        }
+
+        let applied = args.activity.clone();
+        self.discord
+            .update_activity(args)
+            .await
+            .map_err(Error::UpdateActivity)?;
+
+        *self.last_activity.lock().unwrap() = applied;
+        Ok(())
+    }
+
+    pub async fn clear_activity(&self) -> Result<(), Error> {
+        self.discord
+            .clear_activity()
+            .await
+            .map_err(Error::ClearActivity)?;
+
+        *self.last_activity.lock().unwrap() = None;
+        Ok(())
+    }
+
+    /// Stops the connection watcher and closes the connection. Fails when something else still
+    /// holds on to `discord`.
+    pub async fn disconnect(self) -> Result<(), Error> {
+        let watcher = self.watcher.lock().unwrap().take();
+        if let Some(watcher) = watcher {
+            // It may be re-applying the activity, which holds on to `discord` until it's done.
+            watcher.abort();
+            let _ = watcher.await;
+        }
+
+        let discord = Arc::try_unwrap(self.discord).map_err(|_| Error::StillInUse(self.app_id))?;
+        discord.disconnect().await;
+        Ok(())
+    }
+
+    /// Watches for the connection to Discord dropping, for example when the Discord client
+    /// restarts or updates, and restores the last activity once it's back.
+    ///
+    /// discord-sdk re-dials the IPC socket on its own, with its own backoff that it doesn't
+    /// expose. So this only reports `Reconnecting` when the connection drops and `Connected` when
+    /// it is back. The watcher stops once the client is disconnected, a watcher started before
+    /// is stopped.
+    pub fn watch_connection(&self, on_event: impl Fn(ConnectionEvent) + Send + Sync + 'static) {
+        let discord = Arc::downgrade(&self.discord);
+        let last_activity = self.last_activity.clone();
+        let mut user = self.wheel.user();
+
+        let watcher = tokio::spawn(async move {
+            loop {
+                if wait_for_connection_state(&mut user, false).await.is_err() {
+                    return;
+                }
+                if discord.strong_count() == 0 {
+                    return;
+                }
+
+                let reason = match &*user.0.borrow() {
+                    ds::wheel::UserState::Disconnected(err) => err.to_string(),
+                    ds::wheel::UserState::Connected(_) => String::new(),
+                };
+                on_event(ConnectionEvent::Reconnecting { reason });
+
+                if wait_for_connection_state(&mut user, true).await.is_err() {
+                    return;
+                }
+
+                let Some(discord) = discord.upgrade() else {
+                    return;
+                };
+
+                let activity = last_activity.lock().unwrap().clone();
+                if let Some(activity) = activity {
+                    let mut args = ds::activity::ActivityArgs::default();
+                    args.activity = Some(activity);
+                    if let Err(e) = discord.update_activity(args).await {
+                        eprintln!("Failed to restore activity after reconnecting: {}", e);
+                    }
+                }
+                drop(discord);
+
+                println!("Reconnected to Discord");
+                on_event(ConnectionEvent::Connected);
+            }
+        });
+        if let Some(previous) = self.watcher.lock().unwrap().replace(watcher) {
+            previous.abort();
+        }
+    }
 }
 
-pub async fn make_client(app_id: ds::AppId, subs: ds::Subscriptions) -> Client {
+/// Waits until the user state is (or becomes) connected or disconnected.
+///
+/// Fails once the Discord client has shut down.
+async fn wait_for_connection_state(
+    user: &mut ds::wheel::UserSpoke,
+    connected: bool,
+) -> Result<(), tokio::sync::watch::error::RecvError> {
+    loop {
+        let is_connected = matches!(
+            *user.0.borrow_and_update(),
+            ds::wheel::UserState::Connected(_)
+        );
+        if is_connected == connected {
+            return Ok(());
+        }
+        user.0.changed().await?;
+    }
+}
+
+pub async fn make_client(
+    app_id: ds::AppId,
+    subs: ds::Subscriptions,
+    connect_timeout: Duration,
+) -> Result<Client, Error> {
     println!("Creating Discord client with app ID: {}", app_id);
     let (wheel, handler) = ds::wheel::Wheel::new(Box::new(|err| {
         println!("Error: {:?}", err);
@@ -18,19 +262,36 @@ pub async fn make_client(app_id: ds::AppId, subs: ds::Subscriptions) -> Client {
     let mut user = wheel.user();
 
     let discord = ds::Discord::new(ds::DiscordApp::PlainId(app_id), subs, Box::new(handler))
-        .expect("unable to create discord client");
-    user.0.changed().await.unwrap();
+        .map_err(Error::CreateClient)?;
 
-    let user = match &*user.0.borrow() {
-        ds::wheel::UserState::Connected(user) => user.clone(),
-        ds::wheel::UserState::Disconnected(err) => panic!("failed to connect to Discord: {}", err),
+    let connected = match tokio::time::timeout(connect_timeout, user.0.changed()).await {
+        Ok(Ok(())) => match &*user.0.borrow() {
+            ds::wheel::UserState::Connected(user) => Ok(user.clone()),
+            ds::wheel::UserState::Disconnected(err) => Err(Error::Disconnected(err.to_string())),
+        },
+        Ok(Err(_)) => Err(Error::Disconnected(
+            "the Discord event handler stopped".to_string(),
+        )),
+        Err(_) => Err(Error::ConnectTimeout(connect_timeout)),
+    };
+
+    let user = match connected {
+        Ok(user) => user,
+        Err(e) => {
+            // Shut down the IPC task, otherwise it keeps retrying in the background.
+            discord.disconnect().await;
+            return Err(e);
+        }
     };
 
     println!("connected to Discord, local user is {:#?}", user);
 
-    Client {
-        discord,
+    Ok(Client {
+        app_id,
+        discord: Arc::new(discord),
         wheel,
         user,
-    }
+        last_activity: Arc::new(Mutex::new(None)),
+        watcher: Mutex::new(None),
+    })
 }
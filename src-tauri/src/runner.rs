@@ -1,9 +1,31 @@
-use discord_sdk::activity::{ActivityBuilder, ActivityKind};
-use std::{fmt::Error, io::ErrorKind, ops::Deref};
+use discord_sdk::activity::ActivityBuilder;
+use std::num::NonZeroU32;
+use std::time::Duration;
 
 use crate::rpc::{self, Client};
 use serde::Deserialize;
 
+// Limits enforced by Discord, longer values are dropped or truncated server side.
+const MAX_TEXT_LEN: usize = 128;
+const MAX_IMAGE_KEY_LEN: usize = 32;
+const MAX_BUTTONS: usize = 2;
+const MAX_BUTTON_LABEL_LEN: usize = 32;
+const MAX_BUTTON_URL_LEN: usize = 512;
+
+#[derive(Deserialize)]
+pub struct ButtonParams {
+    pub label: String,
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+pub struct SecretsParams {
+    #[serde(rename = "match")]
+    pub match_secret: Option<String>,
+    pub join: Option<String>,
+    pub spectate: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct ActivityParams {
     pub app_id: String,
@@ -13,8 +35,20 @@ pub struct ActivityParams {
     pub large_image_key: Option<String>,
     #[serde(rename = "largeImageText")]
     pub large_image_text: Option<String>,
+    #[serde(rename = "smallImageKey")]
+    pub small_image_key: Option<String>,
+    #[serde(rename = "smallImageText")]
+    pub small_image_text: Option<String>,
     pub timestamp: Option<i64>,
+    pub end_timestamp: Option<i64>,
     pub activity_kind: Option<i32>,
+    pub party_id: Option<String>,
+    pub party_size: Option<u32>,
+    pub party_max: Option<u32>,
+    /// At most two, Discord doesn't allow buttons together with secrets.
+    pub buttons: Option<Vec<ButtonParams>>,
+    pub instance: Option<bool>,
+    pub secrets: Option<SecretsParams>,
 }
 
 pub struct CreateActivityResult {
@@ -22,33 +56,173 @@ pub struct CreateActivityResult {
     pub app_id: u64,
 }
 
-fn to_app_id(app_id: &str) -> Result<u64, std::num::ParseIntError> {
+pub fn to_app_id(app_id: &str) -> Result<u64, rpc::Error> {
     app_id.parse::<u64>().map_err(|e| {
         eprintln!("Failed to parse app_id: {}", e);
-        std::num::ParseIntError::from(e)
+        rpc::Error::InvalidAppId(e.to_string())
     })
 }
 
-pub fn parse_activity_json(activity_json: &str) -> Result<ActivityParams, String> {
+pub fn parse_activity_json(activity_json: &str) -> Result<ActivityParams, rpc::Error> {
     serde_json::from_str(activity_json).map_err(|e| {
         eprintln!("Failed to parse activity JSON: {}", e);
-        format!("Failed to parse activity JSON: {}", e)
+        rpc::Error::InvalidActivity(e.to_string())
     })
 }
 
-pub fn create_activity(activity_json: String) -> Result<CreateActivityResult, String> {
+fn invalid_field(field: &'static str, reason: impl Into<String>) -> rpc::Error {
+    let reason = reason.into();
+    eprintln!("Invalid activity field {}: {}", field, reason);
+    rpc::Error::InvalidActivityField { field, reason }
+}
+
+fn check_len(field: &'static str, value: &str, max: usize) -> Result<(), rpc::Error> {
+    if value.len() > max {
+        return Err(invalid_field(
+            field,
+            format!("{} bytes long, the limit is {}", value.len(), max),
+        ));
+    }
+    Ok(())
+}
+
+/// Image keys are asset names limited to 32 bytes, or URLs Discord proxies.
+fn check_image_key(field: &'static str, key: &str) -> Result<(), rpc::Error> {
+    if key.starts_with("https://") || key.starts_with("http://") {
+        return Ok(());
+    }
+    check_len(field, key, MAX_IMAGE_KEY_LEN)
+}
+
+fn check_button(button: &ButtonParams) -> Result<(), rpc::Error> {
+    if button.label.is_empty() {
+        return Err(invalid_field("buttons", "button label is empty"));
+    }
+    check_len("buttons", &button.label, MAX_BUTTON_LABEL_LEN)?;
+    check_len("buttons", &button.url, MAX_BUTTON_URL_LEN)?;
+    if !button.url.starts_with("https://") && !button.url.starts_with("http://") {
+        return Err(invalid_field(
+            "buttons",
+            format!("URL `{}` must start with http:// or https://", button.url),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks every field against Discord's limits, so bad input fails here instead of being
+/// silently dropped by the SDK or Discord.
+fn validate_activity(activity: &ActivityParams) -> Result<(), rpc::Error> {
+    let text_fields = [
+        ("details", &activity.details),
+        ("state", &activity.state),
+        ("largeImageText", &activity.large_image_text),
+        ("smallImageText", &activity.small_image_text),
+        ("party_id", &activity.party_id),
+    ];
+    for (field, value) in text_fields {
+        if let Some(value) = value {
+            check_len(field, value, MAX_TEXT_LEN)?;
+        }
+    }
+
+    if let Some(key) = &activity.large_image_key {
+        check_image_key("largeImageKey", key)?;
+    }
+    if let Some(key) = &activity.small_image_key {
+        check_image_key("smallImageKey", key)?;
+    }
+    if activity.small_image_text.is_some() && activity.small_image_key.is_none() {
+        return Err(invalid_field("smallImageText", "requires smallImageKey"));
+    }
+
+    if let (Some(start), Some(end)) = (activity.timestamp, activity.end_timestamp) {
+        if end < start {
+            return Err(invalid_field(
+                "end_timestamp",
+                "must not be before the start timestamp",
+            ));
+        }
+    }
+
+    match (activity.party_size, activity.party_max) {
+        (None, None) => {}
+        (Some(size), Some(max)) => {
+            if activity.party_id.is_none() {
+                return Err(invalid_field("party_size", "requires party_id"));
+            }
+            if size == 0 || max == 0 {
+                return Err(invalid_field(
+                    "party_size",
+                    "party sizes must be at least 1",
+                ));
+            }
+            if size > max {
+                return Err(invalid_field(
+                    "party_size",
+                    format!("{} is larger than party_max {}", size, max),
+                ));
+            }
+        }
+        _ => {
+            return Err(invalid_field(
+                "party_size",
+                "party_size and party_max must be set together",
+            ))
+        }
+    }
+
+    if let Some(buttons) = &activity.buttons {
+        if buttons.len() > MAX_BUTTONS {
+            return Err(invalid_field(
+                "buttons",
+                format!(
+                    "{} buttons given, the limit is {}",
+                    buttons.len(),
+                    MAX_BUTTONS
+                ),
+            ));
+        }
+        for button in buttons {
+            check_button(button)?;
+        }
+    }
+
+    if let Some(secrets) = &activity.secrets {
+        if activity.buttons.as_ref().is_some_and(|b| !b.is_empty()) {
+            return Err(invalid_field(
+                "secrets",
+                "Discord does not allow buttons and secrets together",
+            ));
+        }
+        let secret_fields = [
+            ("secrets.match", &secrets.match_secret),
+            ("secrets.join", &secrets.join),
+            ("secrets.spectate", &secrets.spectate),
+        ];
+        for (field, value) in secret_fields {
+            if let Some(value) = value {
+                check_len(field, value, MAX_TEXT_LEN)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn create_activity(activity_json: String) -> Result<CreateActivityResult, rpc::Error> {
     let activity: ActivityParams = parse_activity_json(&activity_json)?;
 
-    let app_id: u64 = to_app_id(&activity.app_id).map_err(|e| {
-        eprintln!("Failed to parse app_id: {}", e);
-        format!("Failed to parse app_id: {}", e)
-    })?;
+    let app_id: u64 = to_app_id(&activity.app_id)?;
+    validate_activity(&activity)?;
 
     let details = activity.details.unwrap_or_default();
     let state = activity.state.unwrap_or_default();
     let large_image_key = activity.large_image_key.unwrap_or_default();
     let large_image_text = activity.large_image_text;
+    let small_image_key = activity.small_image_key.unwrap_or_default();
+    let small_image_text = activity.small_image_text;
     let timestamp = activity.timestamp;
+    let end_timestamp = activity.end_timestamp;
     let activity_kind = activity.activity_kind.unwrap_or(0);
 
     let mut rp: discord_sdk::activity::ActivityBuilder =
@@ -83,10 +257,53 @@ pub fn create_activity(activity_json: String) -> Result<CreateActivityResult, St
         rp = rp.start_timestamp(ts as i64);
     }
 
-    // large_image_key
-    if !large_image_key.is_empty() {
-        rp = rp
-            .assets(rpc::ds::activity::Assets::default().large(&large_image_key, large_image_text));
+    // end_timestamp
+    if let Some(ts) = end_timestamp {
+        rp = rp.end_timestamp(ts);
+    }
+
+    // large and small images
+    if !large_image_key.is_empty() || !small_image_key.is_empty() {
+        let mut assets = rpc::ds::activity::Assets::default();
+        if !large_image_key.is_empty() {
+            assets = assets.large(&large_image_key, large_image_text);
+        }
+        if !small_image_key.is_empty() {
+            assets = assets.small(&small_image_key, small_image_text);
+        }
+        rp = rp.assets(assets);
+    }
+
+    // party
+    if let Some(party_id) = activity.party_id {
+        rp = rp.party(
+            party_id,
+            activity.party_size.and_then(NonZeroU32::new),
+            activity.party_max.and_then(NonZeroU32::new),
+            rpc::ds::activity::PartyPrivacy::Private,
+        );
+    }
+
+    // buttons
+    for button in activity.buttons.unwrap_or_default() {
+        rp = rp.button(rpc::ds::activity::Button {
+            label: button.label,
+            url: button.url,
+        });
+    }
+
+    // instance
+    if let Some(instance) = activity.instance {
+        rp = rp.instance(instance);
+    }
+
+    // secrets
+    if let Some(secrets) = activity.secrets {
+        rp = rp.secrets(rpc::ds::activity::Secrets {
+            r#match: secrets.match_secret,
+            join: secrets.join,
+            spectate: secrets.spectate,
+        });
     }
 
     Ok(CreateActivityResult {
@@ -95,17 +312,234 @@ pub fn create_activity(activity_json: String) -> Result<CreateActivityResult, St
     })
 }
 
-pub async fn set_activity(activity_json: String) -> Result<Client, String> {
+/// Connects a new client and sets the activity on it. Use `Client::update_activity` to change
+/// the presence of a client that is already connected.
+pub async fn set_activity(
+    activity_json: String,
+    connect_timeout: Duration,
+) -> Result<Client, rpc::Error> {
     let activity_result: CreateActivityResult = create_activity(activity_json)?;
     let app_id: i64 = activity_result.app_id as i64;
     let activity_builder = activity_result.activity;
 
-    let client = rpc::make_client(app_id, rpc::ds::Subscriptions::ACTIVITY).await;
-    client
-        .discord
-        .update_activity(activity_builder)
-        .await
-        .map_err(|e| format!("Failed to update activity: {}", e))?;
+    let client =
+        rpc::make_client(app_id, rpc::ds::Subscriptions::ACTIVITY, connect_timeout).await?;
+    if let Err(e) = client.update_activity(activity_builder).await {
+        if let Err(disconnect_error) = client.disconnect().await {
+            eprintln!("{}", disconnect_error);
+        }
+        return Err(e);
+    }
 
     Ok(client)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Validates an activity with the fields in `fields`.
+    fn validate(fields: serde_json::Value) -> Result<(), rpc::Error> {
+        let mut activity = serde_json::json!({ "app_id": "1234567890" });
+        activity
+            .as_object_mut()
+            .unwrap()
+            .extend(fields.as_object().unwrap().clone());
+        validate_activity(&parse_activity_json(&activity.to_string()).unwrap())
+    }
+
+    /// The field `fields` are rejected for.
+    fn invalid_field_of(fields: serde_json::Value) -> &'static str {
+        match validate(fields) {
+            Err(rpc::Error::InvalidActivityField { field, .. }) => field,
+            Err(e) => panic!("unexpected error: {}", e),
+            Ok(()) => panic!("activity was accepted"),
+        }
+    }
+
+    fn button(label: &str, url: &str) -> serde_json::Value {
+        serde_json::json!({ "label": label, "url": url })
+    }
+
+    /// The assets of the activity built from `fields`.
+    fn assets_of(fields: serde_json::Value) -> Option<rpc::ds::activity::Assets> {
+        let mut activity = serde_json::json!({ "app_id": "1234567890", "details": "Playing" });
+        activity
+            .as_object_mut()
+            .unwrap()
+            .extend(fields.as_object().unwrap().clone());
+        let args: rpc::ds::activity::ActivityArgs = create_activity(activity.to_string())
+            .unwrap()
+            .activity
+            .into();
+        args.activity.unwrap().assets
+    }
+
+    #[test]
+    fn accepts_a_full_activity() {
+        validate(serde_json::json!({
+            "details": "Playing",
+            "state": "In a match",
+            "largeImageKey": "https://example.com/large.png",
+            "largeImageText": "Large",
+            "smallImageKey": "small",
+            "smallImageText": "Small",
+            "timestamp": 100,
+            "end_timestamp": 200,
+            "party_id": "party",
+            "party_size": 2,
+            "party_max": 4,
+            "buttons": [button("Website", "https://example.com"), button("Wiki", "http://example.com/wiki")],
+        }))
+        .unwrap();
+        validate(serde_json::json!({})).unwrap();
+    }
+
+    #[test]
+    fn limits_text_length() {
+        let limit = "x".repeat(MAX_TEXT_LEN);
+        validate(serde_json::json!({ "details": limit })).unwrap();
+
+        let too_long = "x".repeat(MAX_TEXT_LEN + 1);
+        assert_eq!(
+            invalid_field_of(serde_json::json!({ "details": too_long })),
+            "details"
+        );
+        assert_eq!(
+            invalid_field_of(serde_json::json!({ "state": too_long })),
+            "state"
+        );
+        assert_eq!(
+            invalid_field_of(serde_json::json!({ "largeImageText": too_long })),
+            "largeImageText"
+        );
+        // Bytes are counted, not characters.
+        let multibyte = "\u{00E9}".repeat(MAX_TEXT_LEN / 2 + 1);
+        assert_eq!(
+            invalid_field_of(serde_json::json!({ "details": multibyte })),
+            "details"
+        );
+    }
+
+    #[test]
+    fn limits_image_keys_but_not_image_urls() {
+        let long_key = "x".repeat(MAX_IMAGE_KEY_LEN + 1);
+        assert_eq!(
+            invalid_field_of(serde_json::json!({ "largeImageKey": long_key })),
+            "largeImageKey"
+        );
+        let long_url = format!("https://example.com/{}", long_key);
+        validate(serde_json::json!({ "largeImageKey": long_url })).unwrap();
+
+        assert_eq!(
+            invalid_field_of(serde_json::json!({ "smallImageText": "Small" })),
+            "smallImageText"
+        );
+    }
+
+    #[test]
+    fn allows_at_most_two_buttons() {
+        let buttons: Vec<serde_json::Value> = (0..3)
+            .map(|i| button(&format!("Button {}", i), "https://example.com"))
+            .collect();
+        assert_eq!(
+            invalid_field_of(serde_json::json!({ "buttons": buttons })),
+            "buttons"
+        );
+        validate(serde_json::json!({ "buttons": buttons[..2] })).unwrap();
+    }
+
+    #[test]
+    fn buttons_need_a_label_and_an_http_url() {
+        for button in [
+            button("", "https://example.com"),
+            button(&"x".repeat(MAX_BUTTON_LABEL_LEN + 1), "https://example.com"),
+            button("Website", "ftp://example.com"),
+            button("Website", "javascript:alert(1)"),
+            button("Website", "example.com"),
+            button(
+                "Website",
+                &format!("https://example.com/{}", "x".repeat(MAX_BUTTON_URL_LEN)),
+            ),
+        ] {
+            assert_eq!(
+                invalid_field_of(serde_json::json!({ "buttons": [button] })),
+                "buttons"
+            );
+        }
+    }
+
+    #[test]
+    fn party_needs_an_id_and_a_consistent_size() {
+        assert_eq!(
+            invalid_field_of(serde_json::json!({ "party_size": 1 })),
+            "party_size"
+        );
+        assert_eq!(
+            invalid_field_of(serde_json::json!({ "party_max": 4 })),
+            "party_size"
+        );
+        assert_eq!(
+            invalid_field_of(serde_json::json!({ "party_size": 1, "party_max": 4 })),
+            "party_size"
+        );
+        assert_eq!(
+            invalid_field_of(
+                serde_json::json!({ "party_id": "p", "party_size": 0, "party_max": 4 })
+            ),
+            "party_size"
+        );
+        assert_eq!(
+            invalid_field_of(
+                serde_json::json!({ "party_id": "p", "party_size": 5, "party_max": 4 })
+            ),
+            "party_size"
+        );
+        assert_eq!(
+            invalid_field_of(serde_json::json!({ "party_id": "x".repeat(MAX_TEXT_LEN + 1) })),
+            "party_id"
+        );
+        validate(serde_json::json!({ "party_id": "p", "party_size": 4, "party_max": 4 })).unwrap();
+    }
+
+    #[test]
+    fn secrets_exclude_buttons() {
+        let secrets = serde_json::json!({ "join": "join-secret", "match": "match-secret" });
+        validate(serde_json::json!({ "secrets": secrets })).unwrap();
+        // An empty button list is no buttons.
+        validate(serde_json::json!({ "secrets": secrets, "buttons": [] })).unwrap();
+
+        assert_eq!(
+            invalid_field_of(serde_json::json!({
+                "secrets": secrets,
+                "buttons": [button("Website", "https://example.com")],
+            })),
+            "secrets"
+        );
+        assert_eq!(
+            invalid_field_of(serde_json::json!({
+                "secrets": { "spectate": "x".repeat(MAX_TEXT_LEN + 1) },
+            })),
+            "secrets.spectate"
+        );
+    }
+
+    #[test]
+    fn end_timestamp_must_not_be_before_the_start() {
+        validate(serde_json::json!({ "timestamp": 100, "end_timestamp": 100 })).unwrap();
+        validate(serde_json::json!({ "end_timestamp": 100 })).unwrap();
+        assert_eq!(
+            invalid_field_of(serde_json::json!({ "timestamp": 200, "end_timestamp": 100 })),
+            "end_timestamp"
+        );
+    }
+
+    #[test]
+    fn activities_without_images_have_no_assets() {
+        assert!(assets_of(serde_json::json!({})).is_none());
+
+        let assets = assets_of(serde_json::json!({ "smallImageKey": "small" })).unwrap();
+        assert_eq!(assets.small_image.as_deref(), Some("small"));
+        assert!(assets.large_image.is_none());
+    }
+}
@@ -0,0 +1,424 @@
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::sync::Mutex;
+
+use crate::detectable::DetectableGame;
+
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+
+// Jaro-Winkler similarity below which a misspelled query does not match.
+const FUZZY_THRESHOLD: f64 = 0.85;
+
+// Aliases rank slightly below the same match on the name.
+const ALIAS_WEIGHT: f64 = 0.9;
+
+// Executable names, like `bin/game.exe`, rank below names and aliases.
+const EXECUTABLE_WEIGHT: f64 = 0.6;
+
+/// Narrows results to games with an executable that matches every given field.
+#[derive(Default, Deserialize)]
+pub struct SearchFilters {
+    /// `win32`, `linux` or `darwin`.
+    #[serde(default)]
+    pub os: Option<String>,
+    #[serde(default)]
+    pub is_launcher: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct SearchPage {
+    /// Starts at 0.
+    #[serde(default)]
+    pub number: usize,
+    #[serde(default = "default_page_size")]
+    pub size: usize,
+}
+
+fn default_page_size() -> usize {
+    DEFAULT_PAGE_SIZE
+}
+
+impl Default for SearchPage {
+    fn default() -> Self {
+        SearchPage {
+            number: 0,
+            size: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct SearchHit {
+    pub game: DetectableGame,
+    /// From 0 to 1, 1 is an exact match on the name.
+    pub score: f64,
+}
+
+#[derive(Serialize)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    /// How many games matched across all pages.
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
+
+struct IndexedGame {
+    game: DetectableGame,
+    name: String,
+    aliases: Vec<String>,
+    executables: Vec<String>,
+}
+
+/// The game list from the last `fetch_gamelist`, with its names prepared for matching.
+#[derive(Default)]
+pub struct SearchIndex {
+    games: Vec<IndexedGame>,
+}
+
+static INDEX: OnceCell<Mutex<SearchIndex>> = OnceCell::new();
+
+fn get_index() -> &'static Mutex<SearchIndex> {
+    INDEX.get_or_init(|| Mutex::new(SearchIndex::default()))
+}
+
+/// Replaces the games `search_games` looks through.
+pub fn set_games(games: &[DetectableGame]) {
+    *get_index().lock().unwrap() = SearchIndex::new(games);
+}
+
+/// Lowercases `text`, drops the symbols game names often carry and collapses whitespace.
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| !matches!(c, '\u{00A9}' | '\u{00AE}' | '\u{2122}'))
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// How well `query` matches `candidate`, both normalized. Exact, prefix and substring matches
+/// rank above fuzzy ones, which tolerate typos.
+fn match_score(query: &str, candidate: &str) -> Option<f64> {
+    if candidate == query {
+        return Some(1.0);
+    }
+    if candidate.starts_with(query) {
+        return Some(0.9);
+    }
+    if candidate.split(' ').any(|word| word.starts_with(query)) {
+        return Some(0.85);
+    }
+    if candidate.contains(query) {
+        return Some(0.8);
+    }
+
+    let words: Vec<&str> = candidate.split(' ').collect();
+    if query
+        .split(' ')
+        .all(|part| words.iter().any(|word| word.starts_with(part)))
+    {
+        return Some(0.75);
+    }
+
+    // Compared against the start of the candidate too, so an unfinished query with a typo
+    // still matches a longer name.
+    let prefix: String = candidate.chars().take(query.chars().count()).collect();
+    let similarity =
+        strsim::jaro_winkler(query, candidate).max(strsim::jaro_winkler(query, &prefix) * 0.95);
+    (similarity >= FUZZY_THRESHOLD).then_some(similarity * 0.7)
+}
+
+impl IndexedGame {
+    fn matches(&self, filters: &SearchFilters) -> bool {
+        if filters.os.is_none() && filters.is_launcher.is_none() {
+            return true;
+        }
+        self.game.executables.iter().any(|executable| {
+            filters
+                .os
+                .as_ref()
+                .is_none_or(|os| executable.os.eq_ignore_ascii_case(os))
+                && filters
+                    .is_launcher
+                    .is_none_or(|is_launcher| executable.is_launcher == is_launcher)
+        })
+    }
+
+    fn score(&self, query: &str) -> Option<f64> {
+        let weighted = |candidates: &[String], weight: f64| {
+            candidates
+                .iter()
+                .filter_map(|candidate| match_score(query, candidate))
+                .map(|score| score * weight)
+                .reduce(f64::max)
+        };
+        [
+            match_score(query, &self.name),
+            weighted(&self.aliases, ALIAS_WEIGHT),
+            weighted(&self.executables, EXECUTABLE_WEIGHT),
+        ]
+        .into_iter()
+        .flatten()
+        .reduce(f64::max)
+    }
+}
+
+impl SearchIndex {
+    pub fn new(games: &[DetectableGame]) -> SearchIndex {
+        SearchIndex {
+            games: games
+                .iter()
+                .map(|game| IndexedGame {
+                    game: game.clone(),
+                    name: normalize(&game.name),
+                    aliases: game.aliases.iter().map(|alias| normalize(alias)).collect(),
+                    executables: game
+                        .executables
+                        .iter()
+                        .map(|executable| normalize(&executable.name))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Ranks the games matching `query` and `filters`, best first, and returns one page. An
+    /// empty query lists every game that passes the filters by name.
+    pub fn search(&self, query: &str, filters: &SearchFilters, page: &SearchPage) -> SearchResults {
+        let query = normalize(query);
+        let mut matched: Vec<(&IndexedGame, f64)> = self
+            .games
+            .iter()
+            .filter(|indexed| indexed.matches(filters))
+            .filter_map(|indexed| {
+                if query.is_empty() {
+                    return Some((indexed, 0.0));
+                }
+                indexed.score(&query).map(|score| (indexed, score))
+            })
+            .collect();
+        matched.sort_by(|(a, a_score), (b, b_score)| {
+            b_score
+                .partial_cmp(a_score)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        let page_size = page.size.clamp(1, MAX_PAGE_SIZE);
+        let hits = matched
+            .iter()
+            .skip(page.number.saturating_mul(page_size))
+            .take(page_size)
+            .map(|(indexed, score)| SearchHit {
+                game: indexed.game.clone(),
+                score: *score,
+            })
+            .collect();
+
+        SearchResults {
+            hits,
+            total: matched.len(),
+            page: page.number,
+            page_size,
+        }
+    }
+}
+
+/// Searches the game list loaded by `fetch_gamelist` by name, aliases and executable names.
+#[tauri::command(rename_all = "snake_case")]
+pub fn search_games(
+    query: String,
+    filters: Option<SearchFilters>,
+    page: Option<SearchPage>,
+) -> SearchResults {
+    get_index().lock().unwrap().search(
+        &query,
+        &filters.unwrap_or_default(),
+        &page.unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(
+        id: &str,
+        name: &str,
+        aliases: &[&str],
+        executables: &[(&str, bool)],
+    ) -> DetectableGame {
+        let executables: Vec<serde_json::Value> = executables
+            .iter()
+            .map(|(os, is_launcher)| {
+                serde_json::json!({ "name": "game.exe", "os": os, "is_launcher": is_launcher })
+            })
+            .collect();
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": name,
+            "aliases": aliases,
+            "executables": executables,
+        }))
+        .unwrap()
+    }
+
+    fn ids(results: &SearchResults) -> Vec<&str> {
+        results
+            .hits
+            .iter()
+            .map(|hit| hit.game.id.as_str())
+            .collect()
+    }
+
+    fn search(index: &SearchIndex, query: &str) -> SearchResults {
+        index.search(query, &SearchFilters::default(), &SearchPage::default())
+    }
+
+    #[test]
+    fn normalizes_case_symbols_and_whitespace() {
+        assert_eq!(normalize("  Portal\u{2122}   2 "), "portal 2");
+        assert_eq!(normalize("FINAL FANTASY\u{00AE} XIV"), "final fantasy xiv");
+    }
+
+    #[test]
+    fn ranks_match_tiers() {
+        assert_eq!(match_score("portal", "portal"), Some(1.0));
+        assert_eq!(match_score("port", "portal 2"), Some(0.9));
+        assert_eq!(match_score("fantasy", "final fantasy xiv"), Some(0.85));
+        assert_eq!(match_score("craf", "minecraft"), Some(0.8));
+        assert_eq!(match_score("fin xiv", "final fantasy xiv"), Some(0.75));
+
+        let fuzzy = match_score("mincraft", "minecraft").unwrap();
+        assert!(fuzzy < 0.75, "{}", fuzzy);
+    }
+
+    #[test]
+    fn fuzzy_matches_need_a_close_enough_name() {
+        // Jaro-Winkler 0.889, above the threshold.
+        let score = match_score("portal", "mortal").unwrap();
+        assert!((score - strsim::jaro_winkler("portal", "mortal") * 0.7).abs() < 1e-9);
+        // Jaro-Winkler 0.844, just below it.
+        assert_eq!(match_score("inside", "insane"), None);
+        assert_eq!(match_score("zelda", "minecraft"), None);
+    }
+
+    #[test]
+    fn results_are_ordered_by_score_then_name() {
+        let index = SearchIndex::new(&[
+            game("1", "Mortal", &[], &[("win32", false)]),
+            game("2", "Portal 2", &[], &[("win32", false)]),
+            game("3", "Portal", &[], &[("win32", false)]),
+            game("4", "Aperture Tag", &["Portal Mod"], &[("win32", false)]),
+            game("5", "Bridge Constructor Portal", &[], &[("win32", false)]),
+        ]);
+
+        let results = search(&index, "portal");
+        assert_eq!(ids(&results), ["3", "2", "5", "4", "1"]);
+        assert_eq!(results.hits[0].score, 1.0);
+        // The alias prefix match is weighed down below a word prefix match on a name.
+        assert!((results.hits[3].score - 0.9 * ALIAS_WEIGHT).abs() < 1e-9);
+        assert_eq!(results.total, 5);
+    }
+
+    #[test]
+    fn matches_executable_names_below_names_and_aliases() {
+        let index = SearchIndex::new(&[
+            game("1", "Hollow Knight", &[], &[("win32", false)]),
+            game("2", "Game Dev Tycoon", &["Game Dev"], &[("win32", false)]),
+        ]);
+
+        // Only its `game.exe` matches the first one.
+        let results = search(&index, "game");
+        assert_eq!(ids(&results), ["2", "1"]);
+        assert_eq!(results.hits[0].score, 0.9);
+        assert!((results.hits[1].score - 0.9 * EXECUTABLE_WEIGHT).abs() < 1e-9);
+
+        let results = search(&index, "game.exe");
+        assert!(results
+            .hits
+            .iter()
+            .all(|hit| hit.score == EXECUTABLE_WEIGHT));
+    }
+
+    #[test]
+    fn empty_query_lists_every_game_by_name() {
+        let index = SearchIndex::new(&[
+            game("1", "Beta", &[], &[("win32", false)]),
+            game("2", "Alpha", &[], &[("win32", false)]),
+        ]);
+        assert_eq!(ids(&search(&index, "  ")), ["2", "1"]);
+    }
+
+    #[test]
+    fn filters_need_one_executable_matching_every_field() {
+        let index = SearchIndex::new(&[
+            game("1", "Game One", &[], &[("win32", false), ("linux", true)]),
+            game("2", "Game Two", &[], &[("linux", false)]),
+            game("3", "Game Three", &[], &[("darwin", false)]),
+        ]);
+        let page = SearchPage::default();
+
+        let linux = SearchFilters {
+            os: Some("Linux".to_string()),
+            is_launcher: None,
+        };
+        assert_eq!(ids(&index.search("game", &linux, &page)), ["1", "2"]);
+
+        let linux_launcher = SearchFilters {
+            os: Some("linux".to_string()),
+            is_launcher: Some(true),
+        };
+        assert_eq!(ids(&index.search("game", &linux_launcher, &page)), ["1"]);
+
+        let win32_launcher = SearchFilters {
+            os: Some("win32".to_string()),
+            is_launcher: Some(true),
+        };
+        assert!(index.search("game", &win32_launcher, &page).hits.is_empty());
+    }
+
+    #[test]
+    fn pages_through_results() {
+        let games: Vec<DetectableGame> = (0..5)
+            .map(|i| {
+                game(
+                    &i.to_string(),
+                    &format!("Game {}", i),
+                    &[],
+                    &[("win32", false)],
+                )
+            })
+            .collect();
+        let index = SearchIndex::new(&games);
+        let filters = SearchFilters::default();
+
+        let second = index.search("game", &filters, &SearchPage { number: 1, size: 2 });
+        assert_eq!(ids(&second), ["2", "3"]);
+        assert_eq!(second.total, 5);
+        assert_eq!(second.page, 1);
+
+        let last = index.search("game", &filters, &SearchPage { number: 2, size: 2 });
+        assert_eq!(ids(&last), ["4"]);
+        let past_the_end = index.search("game", &filters, &SearchPage { number: 9, size: 2 });
+        assert!(past_the_end.hits.is_empty());
+        assert_eq!(past_the_end.total, 5);
+
+        // Page sizes are kept between 1 and the maximum.
+        let empty_page = index.search("game", &filters, &SearchPage { number: 0, size: 0 });
+        assert_eq!(empty_page.page_size, 1);
+        let huge_page = index.search(
+            "game",
+            &filters,
+            &SearchPage {
+                number: 0,
+                size: 1000,
+            },
+        );
+        assert_eq!(huge_page.page_size, MAX_PAGE_SIZE);
+    }
+}
@@ -0,0 +1,94 @@
+use crate::app_dirs;
+use crate::gamelist::GameListSource;
+use crate::install::InstallStrategy;
+use crate::signature::UnsignedPolicy;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Backend settings, saved to `settings.json` in the app config directory.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Settings {
+    /// Where fake games are installed. `None` uses `games` in the app data directory.
+    #[serde(default)]
+    pub games_root: Option<PathBuf>,
+    /// Whether runner copies are hard linked, reflinked or copied from the template.
+    #[serde(default)]
+    pub install_strategy: InstallStrategy,
+    /// Where the game list is loaded from, in order. `None` uses `gamelist::default_sources`.
+    #[serde(default)]
+    pub gamelist_sources: Option<Vec<GameListSource>>,
+    /// Whether lists from signed sources are used when their signature is missing.
+    #[serde(default)]
+    pub unsigned_gamelists: UnsignedPolicy,
+}
+
+static SETTINGS: OnceCell<Mutex<Settings>> = OnceCell::new();
+
+fn get_settings() -> &'static Mutex<Settings> {
+    SETTINGS.get_or_init(|| Mutex::new(Settings::default()))
+}
+
+fn settings_file_path() -> Result<PathBuf, String> {
+    Ok(app_dirs::get()?.config_dir.join("settings.json"))
+}
+
+/// Loads the settings saved by a previous run, keeping the defaults if there are none.
+pub fn restore() {
+    let saved = settings_file_path().and_then(|file_path| {
+        if !file_path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&file_path)
+            .map_err(|e| format!("Failed to read settings file: {}", e))?;
+        serde_json::from_str::<Settings>(&json)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse settings file: {}", e))
+    });
+
+    match saved {
+        Ok(Some(saved)) => *get_settings().lock().unwrap() = saved,
+        Ok(None) => {}
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+pub fn current() -> Settings {
+    get_settings().lock().unwrap().clone()
+}
+
+/// Applies `change` to the settings and saves them.
+pub fn update(change: impl FnOnce(&mut Settings)) -> Result<Settings, String> {
+    let mut settings = get_settings().lock().unwrap();
+    let mut updated = settings.clone();
+    change(&mut updated);
+
+    let file_path = settings_file_path()?;
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app config directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&updated)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(&file_path, json).map_err(|e| format!("Failed to write settings file: {}", e))?;
+
+    *settings = updated.clone();
+    Ok(updated)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_app_settings() -> Settings {
+    current()
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn set_install_strategy(install_strategy: InstallStrategy) -> Result<Settings, String> {
+    update(|settings| settings.install_strategy = install_strategy)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn set_unsigned_gamelists(unsigned_gamelists: UnsignedPolicy) -> Result<Settings, String> {
+    update(|settings| settings.unsigned_gamelists = unsigned_gamelists)
+}
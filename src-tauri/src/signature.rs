@@ -0,0 +1,238 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Base64 ed25519 public key that signed game lists are checked against, set with the
+/// `GAMELIST_PUBLIC_KEY` environment variable when building. A source's own `public_key`
+/// takes precedence.
+pub const EMBEDDED_PUBLIC_KEY: Option<&str> = option_env!("GAMELIST_PUBLIC_KEY");
+
+/// Detached signatures are next to the list, with this appended to its URL or path.
+pub const SIGNATURE_SUFFIX: &str = ".sig";
+
+/// What happens to a list from a signed source when its signature is missing, or when there is
+/// no public key to check it with. An invalid signature is always rejected.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnsignedPolicy {
+    /// Use the list and log a warning.
+    #[default]
+    Warn,
+    Reject,
+}
+
+/// How a served list's signature was checked.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// The source is not signed.
+    #[default]
+    NotChecked,
+    Verified,
+    /// Allowed by `UnsignedPolicy::Warn`.
+    Unsigned {
+        reason: String,
+    },
+}
+
+/// Where the detached signature of a list at `path` is.
+pub fn signature_path(path: &Path) -> PathBuf {
+    let mut signature_path = path.as_os_str().to_owned();
+    signature_path.push(SIGNATURE_SUFFIX);
+    PathBuf::from(signature_path)
+}
+
+/// Reads the detached signature of the list at `path`, `None` if there is none.
+pub fn read_signature_file(path: &Path) -> Result<Option<String>, String> {
+    let signature_path = signature_path(path);
+    match fs::read_to_string(&signature_path) {
+        Ok(signature) => Ok(Some(signature)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {:?}: {}", signature_path, e)),
+    }
+}
+
+fn decode_public_key(public_key: &str) -> Result<VerifyingKey, String> {
+    let bytes = STANDARD
+        .decode(public_key.trim())
+        .map_err(|e| format!("Invalid game list public key: {}", e))?;
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| "Invalid game list public key: expected 32 bytes".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|e| format!("Invalid game list public key: {}", e))
+}
+
+/// Checks a base64 detached ed25519 signature of `body`.
+pub fn verify(body: &[u8], signature: &str, public_key: &str) -> Result<(), String> {
+    let public_key = decode_public_key(public_key)?;
+    let bytes = STANDARD
+        .decode(signature.trim())
+        .map_err(|e| format!("Malformed game list signature: {}", e))?;
+    let signature = Signature::from_slice(&bytes)
+        .map_err(|e| format!("Malformed game list signature: {}", e))?;
+
+    public_key
+        .verify_strict(body, &signature)
+        .map_err(|_| "Invalid game list signature".to_string())
+}
+
+/// Checks a list from a signed source. `signature` is `None` when the source has none, and
+/// `public_key` is the source's own key if it has one.
+pub fn check(
+    body: &[u8],
+    signature: Option<&str>,
+    public_key: Option<&str>,
+    policy: UnsignedPolicy,
+) -> Result<SignatureStatus, String> {
+    let reason = match (public_key.or(EMBEDDED_PUBLIC_KEY), signature) {
+        (Some(public_key), Some(signature)) => {
+            verify(body, signature, public_key)?;
+            return Ok(SignatureStatus::Verified);
+        }
+        (None, _) => "there is no public key to verify the game list signature with",
+        (Some(_), None) => "the game list signature is missing",
+    };
+    unsigned(reason, policy)
+}
+
+/// Applies `policy` to a list from a signed source that can't be verified, `reason` says why.
+pub fn unsigned(reason: &str, policy: UnsignedPolicy) -> Result<SignatureStatus, String> {
+    match policy {
+        UnsignedPolicy::Reject => Err(format!("Rejected game list, {}", reason)),
+        UnsignedPolicy::Warn => {
+            eprintln!("Warning: using the game list although {}", reason);
+            Ok(SignatureStatus::Unsigned {
+                reason: reason.to_string(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const LIST: &[u8] =
+        br#"[{"id":"1","name":"Game","executables":[{"os":"win32","name":"game.exe"}]}]"#;
+
+    // A fixed test keypair, never used to sign real lists.
+    fn test_key() -> SigningKey {
+        SigningKey::from_bytes(&[7; 32])
+    }
+
+    fn public_key(key: &SigningKey) -> String {
+        STANDARD.encode(key.verifying_key().as_bytes())
+    }
+
+    fn sign(key: &SigningKey, body: &[u8]) -> String {
+        STANDARD.encode(key.sign(body).to_bytes())
+    }
+
+    #[test]
+    fn accepts_a_valid_signature() {
+        let key = test_key();
+        let status = check(
+            LIST,
+            Some(&sign(&key, LIST)),
+            Some(&public_key(&key)),
+            UnsignedPolicy::Reject,
+        );
+        assert!(matches!(status, Ok(SignatureStatus::Verified)));
+    }
+
+    #[test]
+    fn rejects_a_tampered_list_under_either_policy() {
+        let key = test_key();
+        let signature = sign(&key, LIST);
+        let tampered = String::from_utf8_lossy(LIST).replace("game.exe", "evil.exe");
+
+        for policy in [UnsignedPolicy::Warn, UnsignedPolicy::Reject] {
+            let status = check(
+                tampered.as_bytes(),
+                Some(&signature),
+                Some(&public_key(&key)),
+                policy,
+            );
+            assert_eq!(status.err().as_deref(), Some("Invalid game list signature"));
+        }
+    }
+
+    #[test]
+    fn rejects_a_signature_from_another_key() {
+        let other = SigningKey::from_bytes(&[9; 32]);
+        let status = check(
+            LIST,
+            Some(&sign(&other, LIST)),
+            Some(&public_key(&test_key())),
+            UnsignedPolicy::Warn,
+        );
+        assert_eq!(status.err().as_deref(), Some("Invalid game list signature"));
+    }
+
+    #[test]
+    fn reports_malformed_signatures_and_keys() {
+        let key = public_key(&test_key());
+        let status = check(LIST, Some("not base64!"), Some(&key), UnsignedPolicy::Warn);
+        assert!(status
+            .unwrap_err()
+            .starts_with("Malformed game list signature"));
+
+        let short = STANDARD.encode([1; 16]);
+        let status = check(LIST, Some(&short), Some(&key), UnsignedPolicy::Warn);
+        assert!(status
+            .unwrap_err()
+            .starts_with("Malformed game list signature"));
+
+        let status = check(LIST, Some(&short), Some("AAAA"), UnsignedPolicy::Warn);
+        assert!(status
+            .unwrap_err()
+            .starts_with("Invalid game list public key"));
+    }
+
+    #[test]
+    fn missing_signature_follows_the_policy() {
+        let key = public_key(&test_key());
+
+        let status = check(LIST, None, Some(&key), UnsignedPolicy::Reject);
+        assert_eq!(
+            status.err().as_deref(),
+            Some("Rejected game list, the game list signature is missing")
+        );
+
+        let status = check(LIST, None, Some(&key), UnsignedPolicy::Warn);
+        assert!(matches!(status, Ok(SignatureStatus::Unsigned { .. })));
+    }
+
+    #[test]
+    fn verifies_a_signed_file_written_to_disk() {
+        let dir = std::env::temp_dir().join(format!("gamelist-signature-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let list_path = dir.join("detectable.json");
+        let key = test_key();
+        fs::write(&list_path, LIST).unwrap();
+        assert_eq!(read_signature_file(&list_path), Ok(None));
+
+        fs::write(
+            signature_path(&list_path),
+            format!("{}\n", sign(&key, LIST)),
+        )
+        .unwrap();
+        assert_eq!(signature_path(&list_path), dir.join("detectable.json.sig"));
+
+        let body = fs::read(&list_path).unwrap();
+        let signature = read_signature_file(&list_path).unwrap();
+        let status = check(
+            &body,
+            signature.as_deref(),
+            Some(&public_key(&key)),
+            UnsignedPolicy::Reject,
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(matches!(status, Ok(SignatureStatus::Verified)));
+    }
+}
@@ -0,0 +1,51 @@
+#![allow(dead_code)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use tauri_app_lib::engine::{GameInstaller, InstallStrategy, InstallerConfig};
+
+/// A folder under the system temp dir that is removed again when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "dqc-{}-{}-{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// An installer for `<dir>/games`, copying from `template`.
+pub fn installer(dir: &TempDir, template: &Path, version: &str) -> GameInstaller {
+    GameInstaller::new(InstallerConfig {
+        games_root: dir.path().join("games"),
+        template_path: template.to_path_buf(),
+        template_version: version.to_string(),
+        install_strategy: InstallStrategy::Copy,
+    })
+}
+
+/// Writes a stand-in runner template, the installer only ever copies and hashes it.
+pub fn fake_template(dir: &TempDir, contents: &str) -> PathBuf {
+    let path = dir.path().join(format!("template-{}", contents));
+    fs::write(&path, contents).unwrap();
+    path
+}
@@ -0,0 +1,280 @@
+mod common;
+
+use common::{fake_template, installer, TempDir};
+use std::fs;
+use std::time::Duration;
+use tauri_app_lib::engine::{current_os, InstallMethod, RunnerManager};
+
+#[test]
+fn install_places_runner_and_records_it() {
+    let dir = TempDir::new("install");
+    let runners = RunnerManager::new();
+    let template = fake_template(&dir, "v1");
+    let installer = installer(&dir, &template, "1.0.0");
+
+    let installed = installer
+        .install(
+            &runners,
+            "bin",
+            "game.exe",
+            100,
+            Some("Game".to_string()),
+            None,
+        )
+        .unwrap();
+    assert_eq!(installed.path, dir.path().join("games/100/bin/game.exe"));
+    assert_eq!(fs::read_to_string(&installed.path).unwrap(), "v1");
+
+    let games = installer.installed(&runners, None);
+    assert_eq!(games.len(), 1);
+    let game = &games[0];
+    assert_eq!(game.app_id, 100);
+    assert_eq!(game.manifest.as_ref().unwrap().name, "Game");
+
+    assert_eq!(game.executables.len(), 1);
+    let executable = &game.executables[0];
+    assert_eq!(executable.name, "bin/game.exe");
+    assert_eq!(executable.path, "bin");
+    assert_eq!(executable.os.as_deref(), Some(current_os()));
+    assert_eq!(executable.template_version.as_deref(), Some("1.0.0"));
+    assert!(!executable.running);
+}
+
+#[test]
+fn reinstall_keeps_matching_runners_and_replaces_outdated_ones() {
+    let dir = TempDir::new("reinstall");
+    let runners = RunnerManager::new();
+    let template = fake_template(&dir, "v1");
+    installer(&dir, &template, "1.0.0")
+        .install(&runners, "", "game.exe", 100, None, None)
+        .unwrap();
+
+    let installed = installer(&dir, &template, "1.0.0")
+        .install(&runners, "", "game.exe", 100, None, None)
+        .unwrap();
+    assert!(installed.kept && installed.strategy.is_none());
+
+    let new_template = fake_template(&dir, "v2");
+    let installed = installer(&dir, &new_template, "2.0.0")
+        .install(&runners, "", "game.exe", 100, None, None)
+        .unwrap();
+    assert!(!installed.kept && installed.strategy == Some(InstallMethod::Copy));
+    assert_eq!(fs::read_to_string(&installed.path).unwrap(), "v2");
+}
+
+#[test]
+fn install_rejects_paths_outside_the_game_folder() {
+    let dir = TempDir::new("install-escape");
+    let runners = RunnerManager::new();
+    let template = fake_template(&dir, "v1");
+    let installer = installer(&dir, &template, "1.0.0");
+
+    assert!(installer
+        .install(&runners, "../escape", "game.exe", 100, None, None)
+        .is_err());
+    assert!(installer
+        .install(&runners, "", "../game.exe", 100, None, None)
+        .is_err());
+    assert!(installer
+        .install(&runners, "/etc", "game.exe", 100, None, None)
+        .is_err());
+    assert!(!dir.path().join("games/escape").exists());
+    assert!(!dir.path().join("games/game.exe").exists());
+}
+
+#[test]
+fn install_fails_without_a_template() {
+    let dir = TempDir::new("install-no-template");
+    let runners = RunnerManager::new();
+    let installer = installer(&dir, &dir.path().join("missing"), "1.0.0");
+
+    assert!(installer
+        .install(&runners, "", "game.exe", 100, None, None)
+        .is_err());
+    // Listing never needs the template.
+    assert!(installer.installed(&runners, None).is_empty());
+}
+
+#[test]
+fn installed_filters_by_app_id() {
+    let dir = TempDir::new("installed-filter");
+    let runners = RunnerManager::new();
+    let template = fake_template(&dir, "v1");
+    let installer = installer(&dir, &template, "1.0.0");
+    installer
+        .install(&runners, "", "a.exe", 2, None, None)
+        .unwrap();
+    installer
+        .install(&runners, "", "b.exe", 1, None, None)
+        .unwrap();
+
+    let app_ids: Vec<i64> = installer
+        .installed(&runners, None)
+        .iter()
+        .map(|game| game.app_id)
+        .collect();
+    assert_eq!(app_ids, [1, 2]);
+
+    let games = installer.installed(&runners, Some(2));
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].executables[0].name, "a.exe");
+}
+
+#[test]
+fn uninstall_removes_an_executable_then_the_game() {
+    let dir = TempDir::new("uninstall");
+    let runners = RunnerManager::new();
+    let template = fake_template(&dir, "v1");
+    let installer = installer(&dir, &template, "1.0.0");
+    installer
+        .install(&runners, "bin", "game.exe", 100, None, None)
+        .unwrap();
+    installer
+        .install(&runners, "", "launcher.exe", 100, None, None)
+        .unwrap();
+
+    installer
+        .uninstall(&runners, 100, Some("bin/game.exe"))
+        .unwrap();
+    let game_dir = dir.path().join("games/100");
+    assert!(!game_dir.join("bin").exists());
+    let games = installer.installed(&runners, Some(100));
+    let names: Vec<&str> = games[0]
+        .executables
+        .iter()
+        .map(|executable| executable.name.as_str())
+        .collect();
+    assert_eq!(names, ["launcher.exe"]);
+    let manifest = games[0].manifest.as_ref().unwrap();
+    assert!(manifest.executable("bin/game.exe").is_none());
+
+    installer.uninstall(&runners, 100, None).unwrap();
+    assert!(!game_dir.exists());
+    assert!(installer.uninstall(&runners, 100, None).is_err());
+}
+
+#[test]
+fn uninstall_rejects_paths_outside_the_game_folder() {
+    let dir = TempDir::new("uninstall-escape");
+    let runners = RunnerManager::new();
+    let template = fake_template(&dir, "v1");
+    let installer = installer(&dir, &template, "1.0.0");
+    installer
+        .install(&runners, "", "game.exe", 100, None, None)
+        .unwrap();
+    installer
+        .install(&runners, "", "game.exe", 200, None, None)
+        .unwrap();
+
+    assert!(installer
+        .uninstall(&runners, 100, Some("../200/game.exe"))
+        .is_err());
+    assert!(dir.path().join("games/200/game.exe").exists());
+}
+
+#[test]
+fn upgrade_replaces_runners_from_an_older_template() {
+    let dir = TempDir::new("upgrade");
+    let runners = RunnerManager::new();
+    let old_template = fake_template(&dir, "v1");
+    installer(&dir, &old_template, "1.0.0")
+        .install(&runners, "", "game.exe", 100, None, None)
+        .unwrap();
+
+    let new_template = fake_template(&dir, "v2");
+    let installer = installer(&dir, &new_template, "2.0.0");
+
+    let report = installer.upgrade(&runners).unwrap();
+    assert_eq!(report.template_version, "2.0.0");
+    assert_eq!(report.upgraded, [dir.path().join("games/100/game.exe")]);
+    assert!(report.skipped.is_empty() && report.failed.is_empty());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("games/100/game.exe")).unwrap(),
+        "v2"
+    );
+    let games = installer.installed(&runners, None);
+    assert_eq!(
+        games[0].executables[0].template_version.as_deref(),
+        Some("2.0.0")
+    );
+
+    assert!(installer.upgrade(&runners).unwrap().upgraded.is_empty());
+}
+
+#[test]
+fn gc_removes_outdated_runners_and_old_games() {
+    let dir = TempDir::new("gc");
+    let runners = RunnerManager::new();
+    let old_template = fake_template(&dir, "v1");
+    installer(&dir, &old_template, "1.0.0")
+        .install(&runners, "bin", "old.exe", 1, None, None)
+        .unwrap();
+
+    let new_template = fake_template(&dir, "v2");
+    let installer = installer(&dir, &new_template, "2.0.0");
+    installer
+        .install(&runners, "", "new.exe", 2, None, None)
+        .unwrap();
+
+    let report = installer.gc(&runners, None).unwrap();
+    assert!(report.removed_games.is_empty());
+    assert_eq!(
+        report.removed_executables,
+        [dir.path().join("games/1/bin/old.exe")]
+    );
+    // Its only runner is gone, so are its manifest and folders.
+    assert!(!dir.path().join("games/1").exists());
+    assert!(dir.path().join("games/2/new.exe").exists());
+
+    // Installed a moment ago and never launched, so a max age of zero expires it.
+    std::thread::sleep(Duration::from_millis(10));
+    let report = installer.gc(&runners, Some(Duration::ZERO)).unwrap();
+    assert_eq!(report.removed_games, [2]);
+    assert!(installer.installed(&runners, None).is_empty());
+}
+
+#[test]
+fn gc_keeps_files_the_manifest_does_not_list() {
+    let dir = TempDir::new("gc-stray");
+    let runners = RunnerManager::new();
+    let template = fake_template(&dir, "v1");
+    let installer = installer(&dir, &template, "1.0.0");
+    installer
+        .install(&runners, "", "game.exe", 1, None, None)
+        .unwrap();
+    let notes = dir.path().join("games/1/notes.exe");
+    fs::write(&notes, "not a runner").unwrap();
+
+    let report = installer.gc(&runners, None).unwrap();
+    assert!(report.removed_executables.is_empty());
+    assert!(notes.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn gc_never_expires_hard_linked_runners_without_a_manifest() {
+    let dir = TempDir::new("gc-hardlink");
+    let runners = RunnerManager::new();
+    let template = fake_template(&dir, "v1");
+    let installer = installer(&dir, &template, "1.0.0");
+    // Installed before manifests existed, so only the files tell their age.
+    fs::create_dir_all(dir.path().join("games/1")).unwrap();
+    fs::hard_link(&template, dir.path().join("games/1/game.exe")).unwrap();
+    fs::create_dir_all(dir.path().join("games/2")).unwrap();
+    fs::copy(&template, dir.path().join("games/2/game.exe")).unwrap();
+
+    std::thread::sleep(Duration::from_millis(10));
+    let report = installer.gc(&runners, Some(Duration::ZERO)).unwrap();
+    assert_eq!(report.removed_games, [2]);
+    assert!(dir.path().join("games/1/game.exe").exists());
+}
+
+#[test]
+fn gc_without_games_root_is_a_no_op() {
+    let dir = TempDir::new("gc-empty");
+    let installer = installer(&dir, &dir.path().join("missing"), "1.0.0");
+
+    let report = installer.gc(&RunnerManager::new(), None).unwrap();
+    assert!(report.removed_games.is_empty());
+    assert!(report.removed_executables.is_empty());
+}
@@ -0,0 +1,68 @@
+// None of these reach Discord: they cover what the manager does before it would connect, and
+// the state it reports without clients.
+
+use std::time::Duration;
+use tauri_app_lib::engine::{ConnectionState, PresenceManager};
+
+const ACTIVITY: &str = r#"{"app_id": "1234567890", "details": "Playing"}"#;
+
+#[test]
+fn status_of_an_app_without_client_is_disconnected() {
+    let presence = PresenceManager::new();
+
+    let status = presence.status(1234567890);
+    assert_eq!(status.app_id, "1234567890");
+    assert_eq!(status.state, ConnectionState::Disconnected);
+    assert!(status.session.is_none());
+    assert_eq!(
+        serde_json::to_value(&status).unwrap(),
+        serde_json::json!({ "app_id": "1234567890", "state": "disconnected" })
+    );
+
+    assert!(presence.list().is_empty());
+    assert!(!presence.is_connected(1234567890));
+}
+
+#[tokio::test]
+async fn connect_rejects_invalid_activities() {
+    let presence = PresenceManager::new();
+    let timeout = Duration::from_millis(100);
+
+    let error = presence
+        .connect("not json".to_string(), timeout, |_| {})
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), "invalid_activity");
+
+    let error = presence
+        .connect(r#"{"app_id": "abc"}"#.to_string(), timeout, |_| {})
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), "invalid_app_id");
+
+    assert!(presence.list().is_empty());
+}
+
+#[tokio::test]
+async fn updates_need_a_connected_client() {
+    let presence = PresenceManager::new();
+
+    let error = presence
+        .update_activity(ACTIVITY.to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(error.kind(), "not_connected");
+
+    let error = presence.clear_activity(1234567890).await.unwrap_err();
+    assert_eq!(error.kind(), "not_connected");
+}
+
+#[tokio::test]
+async fn disconnecting_without_clients_is_a_no_op() {
+    let presence = PresenceManager::new();
+
+    assert!(!presence.disconnect(1234567890).await.unwrap());
+    presence.end_session(1234567890).await.unwrap();
+    presence.disconnect_all().await.unwrap();
+    assert!(presence.list().is_empty());
+}
@@ -0,0 +1,405 @@
+// Runners are only confirmed through `/proc` before they are signalled, and the Linux runner is
+// the one these tests build, so they only run on Linux.
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{installer, TempDir};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::mpsc;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri_app_lib::engine::{GameInstaller, RunnerManager};
+
+/// Builds the Linux runner once, so the runners started here are the real thing.
+fn runner_template() -> PathBuf {
+    static TEMPLATE: OnceLock<PathBuf> = OnceLock::new();
+    TEMPLATE
+        .get_or_init(|| {
+            let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            let target_dir = std::env::temp_dir().join("dqc-runner-template");
+            let status = Command::new(env!("CARGO"))
+                .arg("build")
+                .arg("--quiet")
+                .arg("--manifest-path")
+                .arg(manifest_dir.join("../src-linux-rust-sleeper/Cargo.toml"))
+                .arg("--target-dir")
+                .arg(&target_dir)
+                .status()
+                .expect("Failed to run cargo");
+            assert!(status.success(), "Failed to build the Linux runner");
+            target_dir.join("debug/discord-quest-runner-linux")
+        })
+        .clone()
+}
+
+fn installed(dir: &TempDir, app_id: i64) -> GameInstaller {
+    let installer = installer(dir, &runner_template(), "1.0.0");
+    installer
+        .install(
+            &RunnerManager::new(),
+            "bin",
+            "game",
+            app_id,
+            Some("Game".to_string()),
+            None,
+        )
+        .unwrap();
+    installer
+}
+
+#[test]
+fn run_for_stops_the_runner_and_records_the_launch() {
+    let dir = TempDir::new("run-for");
+    let installer = installed(&dir, 100);
+    let runners = RunnerManager::new();
+
+    let spawned = runners
+        .spawn(&installer, "Game", "bin", "game", 100)
+        .unwrap()
+        .unwrap();
+    let pid = spawned.runner.pid;
+
+    let games = installer.installed(&runners, None);
+    let launches = &games[0].manifest.as_ref().unwrap().launches;
+    assert_eq!(launches.len(), 1);
+    assert_eq!(launches[0].pid, Some(pid));
+    assert_eq!(launches[0].executable, "bin/game");
+    assert!(launches[0].ended.is_none());
+    // Another process could tell it is running from the manifest.
+    let recorded = runners.recorded(&installer);
+    assert_eq!(recorded.len(), 1);
+    assert_eq!(recorded[0].0.pid, pid);
+
+    let exit = spawned.run_for(Duration::from_millis(200)).unwrap();
+    assert!(exit.runner.stop_requested);
+    assert!(!exit.crashed());
+
+    let games = installer.installed(&runners, None);
+    let launch = &games[0].manifest.as_ref().unwrap().launches[0];
+    assert!(launch.ended.is_some());
+    assert!(runners.recorded(&installer).is_empty());
+}
+
+#[test]
+fn launched_runners_are_tracked_until_stopped() {
+    let dir = TempDir::new("launch");
+    let installer = installed(&dir, 100);
+    let runners = RunnerManager::new();
+
+    let (exited, on_exit) = mpsc::channel();
+    let runner = runners
+        .launch(&installer, "Game", "bin", "game", 100, move |exit| {
+            exited.send(exit).unwrap();
+        })
+        .unwrap()
+        .unwrap();
+
+    assert!(runners.is_active(runner.pid));
+    assert!(runners.has_running());
+    let games = installer.installed(&runners, Some(100));
+    let executable = &games[0].executables[0];
+    assert!(executable.running);
+    assert_eq!(executable.pid, Some(runner.pid));
+
+    runners.stop(&installer, "game", Some(100)).unwrap();
+    let exit = on_exit.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(exit.runner.pid, runner.pid);
+    assert!(exit.runner.stop_requested);
+    assert!(!exit.crashed());
+
+    assert!(!runners.is_active(runner.pid));
+    assert!(!runners.has_running());
+    let games = installer.installed(&runners, Some(100));
+    assert!(!games[0].executables[0].running);
+}
+
+#[test]
+fn stop_leaves_other_apps_running() {
+    let dir = TempDir::new("stop-app");
+    let installer = installed(&dir, 100);
+    let runners = RunnerManager::new();
+    installer
+        .install(&runners, "bin", "game", 200, None, None)
+        .unwrap();
+
+    let (exited, on_exit) = mpsc::channel();
+    let first = runners
+        .launch(&installer, "Game", "bin", "game", 100, |_| {})
+        .unwrap()
+        .unwrap();
+    let second = runners
+        .launch(&installer, "Game", "bin", "game", 200, move |exit| {
+            exited.send(exit).unwrap();
+        })
+        .unwrap()
+        .unwrap();
+
+    runners.stop(&installer, "game", Some(200)).unwrap();
+    let exit = on_exit.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(exit.runner.pid, second.pid);
+    assert!(runners.is_active(first.pid));
+
+    runners.stop_pid(Some(first.pid), "game").unwrap();
+    assert!(!runners.is_active(first.pid));
+}
+
+#[test]
+fn killed_runners_count_as_crashed() {
+    let dir = TempDir::new("crash");
+    let installer = installed(&dir, 100);
+    let runners = RunnerManager::new();
+
+    let (exited, on_exit) = mpsc::channel();
+    let runner = runners
+        .launch(&installer, "Game", "bin", "game", 100, move |exit| {
+            exited.send(exit).unwrap();
+        })
+        .unwrap()
+        .unwrap();
+
+    let status = Command::new("kill")
+        .arg("-KILL")
+        .arg(runner.pid.to_string())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let exit = on_exit.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(exit.signal, Some(9));
+    assert!(exit.crashed());
+}
+
+#[test]
+fn uninstall_stops_the_runners_it_removes() {
+    let dir = TempDir::new("uninstall-running");
+    let installer = installed(&dir, 100);
+    let runners = RunnerManager::new();
+
+    let (exited, on_exit) = mpsc::channel();
+    runners
+        .launch(&installer, "Game", "bin", "game", 100, move |exit| {
+            exited.send(exit).unwrap();
+        })
+        .unwrap()
+        .unwrap();
+
+    installer.uninstall(&runners, 100, None).unwrap();
+    let exit = on_exit.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(exit.runner.stop_requested);
+    assert!(!runners.has_running());
+    assert!(!dir.path().join("games/100").exists());
+}
+
+#[test]
+fn upgrade_skips_running_runners() {
+    let dir = TempDir::new("upgrade-running");
+    let installer = installed(&dir, 100);
+    let runners = RunnerManager::new();
+    let runner = runners
+        .launch(&installer, "Game", "bin", "game", 100, |_| {})
+        .unwrap()
+        .unwrap();
+
+    // Another template makes the installed runner outdated.
+    let template = dir.path().join("template");
+    std::fs::write(&template, "v2").unwrap();
+    let upgrade = common::installer(&dir, &template, "2.0.0")
+        .upgrade(&runners)
+        .unwrap();
+    assert_eq!(upgrade.skipped, [dir.path().join("games/100/bin/game")]);
+    assert!(upgrade.upgraded.is_empty());
+
+    runners.stop_pid(Some(runner.pid), "game").unwrap();
+}
+
+#[test]
+fn gc_skips_running_runners() {
+    let dir = TempDir::new("gc-running");
+    let installer = installed(&dir, 100);
+    let runners = RunnerManager::new();
+    let runner = runners
+        .launch(&installer, "Game", "bin", "game", 100, |_| {})
+        .unwrap()
+        .unwrap();
+
+    let template = dir.path().join("template");
+    std::fs::write(&template, "v2").unwrap();
+    let outdated = common::installer(&dir, &template, "2.0.0");
+    let report = outdated.gc(&runners, None).unwrap();
+    assert_eq!(report.skipped, [dir.path().join("games/100/bin/game")]);
+    assert!(report.removed_executables.is_empty());
+
+    // Nor is a running game removed once it expires.
+    let report = outdated.gc(&runners, Some(Duration::ZERO)).unwrap();
+    assert!(report.removed_games.is_empty());
+    assert!(dir.path().join("games/100/bin/game").exists());
+
+    runners.stop_pid(Some(runner.pid), "game").unwrap();
+}
+
+#[test]
+fn upgrade_skips_runners_started_by_another_process() {
+    let dir = TempDir::new("upgrade-recorded");
+    let installer = installed(&dir, 100);
+    let launcher = RunnerManager::new();
+    let runner = launcher
+        .launch(&installer, "Game", "bin", "game", 100, |_| {})
+        .unwrap()
+        .unwrap();
+
+    let template = dir.path().join("template");
+    std::fs::write(&template, "v2").unwrap();
+    let upgrade = common::installer(&dir, &template, "2.0.0")
+        .upgrade(&RunnerManager::new())
+        .unwrap();
+    assert_eq!(upgrade.skipped, [dir.path().join("games/100/bin/game")]);
+    assert!(upgrade.upgraded.is_empty());
+
+    launcher.stop_pid(Some(runner.pid), "game").unwrap();
+}
+
+#[test]
+fn uninstall_stops_runners_started_by_another_process() {
+    let dir = TempDir::new("uninstall-recorded");
+    let installer = installed(&dir, 100);
+    let launcher = RunnerManager::new();
+    let (exited, on_exit) = mpsc::channel();
+    let runner = launcher
+        .launch(&installer, "Game", "bin", "game", 100, move |exit| {
+            exited.send(exit).unwrap();
+        })
+        .unwrap()
+        .unwrap();
+
+    installer
+        .uninstall(&RunnerManager::new(), 100, Some("bin/game"))
+        .unwrap();
+    let exit = on_exit.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert_eq!(exit.runner.pid, runner.pid);
+    // The manifest records the exit, so nothing is left running.
+    assert!(launcher.recorded(&installer).is_empty());
+    assert!(!dir.path().join("games/100/bin").exists());
+}
+
+#[test]
+fn reinstalling_never_replaces_a_running_runner() {
+    let dir = TempDir::new("reinstall-running");
+    let installer = installed(&dir, 100);
+    let launcher = RunnerManager::new();
+    let runner = launcher
+        .launch(&installer, "Game", "bin", "game", 100, |_| {})
+        .unwrap()
+        .unwrap();
+
+    // The same template leaves the running runner in place.
+    let installed = installer
+        .install(&launcher, "bin", "game", 100, None, None)
+        .unwrap();
+    assert!(installed.kept && installed.strategy.is_none());
+
+    // Another one would orphan it, whichever manager started it.
+    let template = dir.path().join("template");
+    std::fs::write(&template, "v2").unwrap();
+    let outdated = common::installer(&dir, &template, "2.0.0");
+    assert!(outdated
+        .install(&launcher, "bin", "game", 100, None, None)
+        .is_err());
+    assert!(outdated
+        .install(&RunnerManager::new(), "bin", "game", 100, None, None)
+        .is_err());
+
+    assert_eq!(launcher.recorded(&installer)[0].0.pid, runner.pid);
+    launcher.stop_pid(Some(runner.pid), "game").unwrap();
+    assert!(!launcher.is_active(runner.pid));
+}
+
+#[test]
+fn installed_marks_runners_started_by_another_process() {
+    let dir = TempDir::new("installed-recorded");
+    let installer = installed(&dir, 100);
+    let launcher = RunnerManager::new();
+    let runner = launcher
+        .launch(&installer, "Game", "bin", "game", 100, |_| {})
+        .unwrap()
+        .unwrap();
+
+    let games = installer.installed(&RunnerManager::new(), Some(100));
+    let executable = &games[0].executables[0];
+    assert!(executable.running);
+    assert_eq!(executable.pid, Some(runner.pid));
+
+    launcher.stop_pid(Some(runner.pid), "game").unwrap();
+}
+
+#[test]
+fn recorded_runners_can_be_stopped_by_another_manager() {
+    let dir = TempDir::new("recorded");
+    let installer = installed(&dir, 100);
+
+    // The manager that launched it stands in for another process, like the app or the CLI.
+    let launcher = RunnerManager::new();
+    let runner = launcher
+        .launch(&installer, "Game", "bin", "game", 100, |_| {})
+        .unwrap()
+        .unwrap();
+
+    let runners = RunnerManager::new();
+    let recorded: Vec<_> = runners
+        .recorded(&installer)
+        .into_iter()
+        .map(|(runner, _)| runner)
+        .collect();
+    assert_eq!(recorded.len(), 1);
+    assert_eq!(recorded[0].pid, runner.pid);
+
+    let report = runners.stop_recorded(recorded);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_eq!(report.stopped.len(), 1);
+    assert!(runners.recorded(&installer).is_empty());
+}
+
+#[test]
+fn stop_falls_back_to_recorded_runners() {
+    let dir = TempDir::new("stop-recorded");
+    let installer = installed(&dir, 100);
+    let launcher = RunnerManager::new();
+    let runner = launcher
+        .launch(&installer, "Game", "bin", "game", 100, |_| {})
+        .unwrap()
+        .unwrap();
+
+    // A new manager, as after a restart, has nothing registered.
+    let runners = RunnerManager::new();
+    runners.stop(&installer, "game", Some(200)).unwrap();
+    assert!(launcher.is_active(runner.pid));
+
+    runners.stop(&installer, "game", Some(100)).unwrap();
+    assert!(runners.recorded(&installer).is_empty());
+}
+
+#[test]
+fn stop_refuses_runners_whose_file_was_replaced() {
+    let dir = TempDir::new("stop-replaced");
+    let installer = installed(&dir, 100);
+    let runners = RunnerManager::new();
+    let runner = runners
+        .launch(&installer, "Game", "bin", "game", 100, |_| {})
+        .unwrap()
+        .unwrap();
+
+    // `/proc/<pid>/exe` now reads "(deleted)", so the runner can't be confirmed.
+    let target = dir.path().join("games/100/bin/game");
+    let replacement = dir.path().join("games/100/bin/replacement");
+    std::fs::write(&replacement, "v2").unwrap();
+    std::fs::rename(&replacement, &target).unwrap();
+    assert!(runners.stop(&installer, "game", Some(100)).is_err());
+
+    let status = Command::new("kill")
+        .arg("-KILL")
+        .arg(runner.pid.to_string())
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
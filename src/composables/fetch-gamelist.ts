@@ -1,113 +1,88 @@
-import { Game } from '@/types/types';
-import { fetch, ClientOptions } from '@tauri-apps/plugin-http';
-import { tryOnMounted, useAsyncState } from '@vueuse/core';
+import { Game, FetchedGameList, GameListCacheInfo, GameListChanges, GameListSourceFailure } from '@/types/types';
+import { tryOnMounted, tryOnUnmounted, useAsyncState } from '@vueuse/core';
 import { ref, watch } from 'vue';
-import { message } from '@tauri-apps/plugin-dialog'; 
+import { message } from '@tauri-apps/plugin-dialog';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { useGlobalState } from './app-state';
 
 export function useFetchGameList() {
     const { addLog } = useGlobalState();
-    async function fetchGameListGHMirror() {
-        addLog('Fetching game list from GitHub mirror...'); 
-        const response = await invoke('fetch_gamelist_gh_mirror');
-        return response as Game[] | unknown[] | undefined;
-    }
-    async function fetchGameListFromDiscord (){
-        addLog('Fetching game list directly from discord...'); 
-        const response = await invoke('fetch_gamelist_from_discord');
-        return response as Game[] | unknown[] | undefined;
-    };
-
-    // const fetchBundledGameList = fetch(window.location.origin+'/gamelist.json', { method: 'GET' });
-
-    const { 
-        state: gameListGHMirror,
-        error: errorGH,
-        isReady: isReadyGH,
-        execute: executeGH,
-        isLoading: isLoadingGH
-    } = useAsyncState<Game[] | unknown[] | undefined>(fetchGameListGHMirror, [], {
-            immediate: false,
-            resetOnExecute: true,
-        });
-    const { 
-        state: gameListFromDiscord, 
-        error: errorDiscord,
-        isReady: isReadyDiscord,
-        execute: executeDiscord,
-        isLoading: isLoadingDiscord
-    } = useAsyncState(fetchGameListFromDiscord, [], {
-        immediate: false,
-        resetOnExecute: true,
+
+    // Where the game list in use came from and how old it is.
+    const gameListInfo = ref<GameListCacheInfo | null>(null);
+    // Sources the backend tried before the one that worked.
+    const sourceFailures = ref<GameListSourceFailure[]>([]);
+
+    // What the last download changed, new quests usually come with new or changed games.
+    const gameListChanges = ref<GameListChanges | null>(null);
+
+    const unlistenGameListChanged = listen<GameListChanges>('gamelist_changed', ({ payload }) => {
+        gameListChanges.value = payload;
+        const added = payload.added.map(game => game.name).join(', ');
+        addLog(`Game list changed: ${payload.added.length} added, ${payload.removed.length} removed, ${payload.changed.length} changed.`);
+        if (added) {
+            addLog(`New games: ${added}`);
+        }
     });
-    const { 
-        state: bundledGameList,
-        error: errorBundled,
-        isReady: isReadyBundled,
-        execute: executeBundled,
-        isLoading: isLoadingBundled
-    } = useAsyncState(() => {
-        const result = import('../assets/gamelist.json').then(res=>res.default);
-        addLog('Fetching bundled game list for fallback...');
-        return result;
-    }, [], {
+
+    // The backend tries the configured sources in order (GitHub mirror, Discord, cache and
+    // bundled list by default), see `set_gamelist_sources`.
+    async function fetchGameListFromBackend() {
+        addLog('Fetching game list...');
+        const response = await invoke<FetchedGameList>('fetch_gamelist');
+        const { source, etag, last_modified, fetched, checked } = response;
+        gameListInfo.value = { source, etag, last_modified, fetched, checked };
+        sourceFailures.value = response.failures;
+
+        for (const failure of response.failures) {
+            addLog('error', `Game list source ${failure.source} failed: ${failure.error}`);
+        }
+        if (response.signature.status === 'unsigned') {
+            addLog('error', `The game list from ${source} is not verified: ${response.signature.reason}.`);
+        }
+        if (response.rejected > 0) {
+            addLog('debug', `Skipped ${response.rejected} invalid entries from the game list.`);
+        }
+        if (response.from_cache) {
+            const checkedAt = new Date(checked * 1000).toLocaleString();
+            addLog(`Using cached game list from ${source}, last checked ${checkedAt}.`);
+        }
+        addLog(`Using game list from ${source}. ${response.games.length} entries.`);
+        return response.games;
+    }
+
+    const {
+        error: errorGameList,
+        isReady,
+        execute,
+        isLoading
+    } = useAsyncState<Game[]>(fetchGameListFromBackend, [], {
         immediate: false,
         resetOnExecute: true,
     });
 
     const fetchError = ref<string | null>(null);
 
-    const gameDB = ref<Game[]>([]);
+    // Bumped after every fetch, the backend rebuilt its search index by then.
+    const searchIndexVersion = ref(0);
 
     const allFetchDone = ref(false);
 
-    function isValidGameList(data: any): boolean {
-        return Array.isArray(data) && data[0] && 'aliases' in data[0] && 'name' in data[0] && 'executables' in data[0];
-    }
-
-    watch(() => isReadyGH.value, async (newVal) => {
-        addLog('debug','isReadyGH: ' + newVal); 
-    });
-
-    watch(() => isReadyDiscord.value, async (newVal) => {
-        addLog('debug','isReadyDiscord: ' + newVal);
-    })
-    
-    watch(() => isReadyBundled.value, async (newVal) => {
-        addLog('debug','isReadyBundled: ' + newVal); 
+    watch(() => isReady.value, async (newVal) => {
+        addLog('debug','isReady: ' + newVal);
     });
 
     let timeoutId: ReturnType<typeof setTimeout> | null = null;
-    async function fetchGameList() { 
+    async function fetchGameList() {
         allFetchDone.value = false;
-        addLog('Fetching game list...');
-        // try fetching from the Github mirror first, then Discord. Use bundled as fallback.
-        try {
-           await Promise.all([executeGH(), executeBundled()]);
-        } catch {
-            addLog('error', 'Error executing fetch for GitHub mirror or bundled game list.');
-        }
+        fetchError.value = null;
+        await execute();
 
-        if (errorGH.value) { 
-            fetchError.value = 'Error fetching game list from GitHub mirror.';
-            addLog('error','Error fetching game list from GitHub mirror');
-            await executeDiscord();
-            if (errorDiscord.value) {
-                fetchError.value = 'Error fetching game list from Discord.';
-                addLog('error','Error fetching game list from Discord:');
-                if (errorBundled.value) {
-                    fetchError.value = 'Error fetching bundled game list.';
-                    addLog('error','Error fetching bundled game list:');
-                }
-            }
-        }
-        // silently log error for bundled, as it's the last resort.
-        if (errorBundled.value) {
-            addLog('error','Error fetching bundled game list');
-        }
-
-        if (fetchError.value) {
+        if (errorGameList.value) {
+            // Every configured source failed. The backend searches its bundled list then.
+            fetchError.value = String(errorGameList.value);
+            addLog('error', 'Error fetching game list: ' + fetchError.value);
             await message('There was an error fetching the latest game list.' + fetchError.value, {
                 title: 'Game List Fetch Error',
                 kind: 'error',
@@ -116,21 +91,10 @@ export function useFetchGameList() {
                 }
             });
         }
-
-        if (gameListGHMirror.value && gameListGHMirror.value?.length > 0 && isValidGameList(gameListGHMirror.value)) {
-            gameDB.value = gameListGHMirror.value as Game[] || [];
-            addLog('Using game list from GitHub mirror. ' + gameListGHMirror.value.length + ' entries.');
-        } else if (gameListFromDiscord.value && gameListFromDiscord.value?.length > 0 && isValidGameList(gameListFromDiscord.value)) {
-            gameDB.value = gameListFromDiscord.value as Game[] || [];
-            addLog('Using game list from Discord. ' + gameListFromDiscord.value.length + ' entries.');
-        } else {
-            // bundled is always present.
-            addLog('Using bundled game list as fallback.' + bundledGameList.value.length + ' entries.');
-            gameDB.value = bundledGameList.value;
-        }
+        searchIndexVersion.value++;
 
         // Set a timeout to delay setting allFetchDone to true, to allow UI to update.
-      
+
         timeoutId = setTimeout(() => {
             allFetchDone.value = true;
         }, 1800);
@@ -144,23 +108,24 @@ export function useFetchGameList() {
     });
 
     tryOnMounted(async () => {
+        gameListChanges.value = await invoke<GameListChanges | null>('get_gamelist_changes');
         await fetchGameList();
     });
 
+    tryOnUnmounted(() => {
+        unlistenGameListChanged.then(unlisten => unlisten());
+    });
+
 
     return {
-        gameListGHMirror,
-        gameListFromDiscord,
-        bundledGameList,
         fetchError,
-        isReadyGH,
-        isReadyDiscord,
-        isReadyBundled,
-        gameDB,
+        isReady,
+        isLoading,
+        searchIndexVersion,
         fetchGameList,
-        isLoadingGH,
-        isLoadingDiscord,
-        isLoadingBundled,
-        allFetchDone
+        allFetchDone,
+        gameListInfo,
+        sourceFailures,
+        gameListChanges
     }
-}
\ No newline at end of file
+}
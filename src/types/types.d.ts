@@ -16,13 +16,190 @@ export interface Game {
     executables: GameExecutable[];
     aliases?: string[];
     themes?: string[];
+    icon_hash?: string | null;
+    hook?: boolean;
+    overlay?: boolean;
     is_running?: boolean;
     is_installed?: boolean;
 }
 
+/** A validated game list, see `detectable::GameList`. */
+export interface GameList {
+  games: Game[];
+  /** Entries that did not parse or had no installable executable. */
+  rejected: number;
+}
+
+/** Where the cached game list came from, see `gamelist::CacheInfo`. Times are unix seconds. */
+export interface GameListCacheInfo {
+  source: string;
+  etag: string | null;
+  last_modified: string | null;
+  fetched: number;
+  checked: number;
+}
+
+export interface GameListSourceFailure {
+  source: string;
+  error: string;
+  attempts: number;
+}
+
+/** How a game list's detached signature was checked. */
+export type GameListSignatureStatus =
+  | { status: 'not_checked' }
+  | { status: 'verified' }
+  | { status: 'unsigned'; reason: string };
+
+/** What `fetch_gamelist` returns, from the first configured source that had a valid list. */
+export interface FetchedGameList extends GameList, GameListCacheInfo {
+  from_cache: boolean;
+  signature: GameListSignatureStatus;
+  failures: GameListSourceFailure[];
+}
+
+/** Sources with `signed` are checked against `<url>.sig` or `<path>.sig`. */
+interface GameListSignatureConfig {
+  signed?: boolean;
+  /** Base64 ed25519 public key, defaults to the one built into the app. */
+  public_key?: string;
+}
+
+/** An entry of the `set_gamelist_sources` list. */
+export type GameListSource =
+  | ({ type: 'http'; url: string; timeout_secs?: number; retries?: number } & GameListSignatureConfig)
+  | ({ type: 'file'; path: string } & GameListSignatureConfig)
+  | { type: 'cache' }
+  | { type: 'bundled' };
+
+/** A game in `GameListChanges`. */
+export interface GameSummary {
+  id: string;
+  name: string;
+}
+
+/** What a game list download changed, see `get_gamelist_changes` and `gamelist_changed`. */
+export interface GameListChanges {
+  added: GameSummary[];
+  removed: GameSummary[];
+  changed: (GameSummary & {
+    added_executables: GameExecutable[];
+    removed_executables: GameExecutable[];
+  })[];
+  previous_source: string;
+  previous_fetched: number;
+  source: string;
+  fetched: number;
+}
+
+/** Filters for `search_games`, a game matches when one executable passes all of them. */
+export interface SearchFilters {
+  os?: 'win32' | 'linux' | 'darwin';
+  is_launcher?: boolean;
+}
+
+export interface SearchHit {
+  game: Game;
+  /** From 0 to 1, 1 is an exact match on the name. */
+  score: number;
+}
+
+export interface SearchResults {
+  hits: SearchHit[];
+  total: number;
+  page: number;
+  page_size: number;
+}
+
 export interface GameActionsProvider {
   canPlayGame: (game: Game | null) => boolean;
   isGameInstalled: (game: Game | null) => boolean;
   isExecutableRunning: (executable: GameExecutable) => boolean;
   isGameExecutableInstalled: (executable: GameExecutable) => boolean;
-}
\ No newline at end of file
+}
+
+export interface RunnerPayload {
+  app_id: number;
+  executable_name: string;
+  executable_path: string;
+  pid: number;
+}
+
+export interface RunnerExitedPayload extends RunnerPayload {
+  code: number | null;
+  signal: number | null;
+  requested: boolean;
+  error?: string;
+}
+
+export interface ClientErrorPayload {
+  app_id: string | null;
+  kind: string;
+  reason: string;
+}
+
+/** The `activity_json` accepted by the rpc_* commands, see `runner::ActivityParams`. */
+export interface ActivityParams {
+  app_id: string;
+  details?: string;
+  state?: string;
+  largeImageKey?: string;
+  largeImageText?: string;
+  smallImageKey?: string;
+  smallImageText?: string;
+  timestamp?: number;
+  end_timestamp?: number;
+  activity_kind?: number;
+  party_id?: string;
+  party_size?: number;
+  party_max?: number;
+  buttons?: { label: string; url: string }[];
+  instance?: boolean;
+  secrets?: { match?: string; join?: string; spectate?: string };
+}
+
+export interface InstalledExecutable {
+  /** Relative to the game's folder, `/` separated like the detectable list's names. */
+  name: string;
+  executable_name: string;
+  path: string;
+  full_path: string;
+  size: number | null;
+  created: number | null;
+  running: boolean;
+  pid: number | null;
+  /** From the game's manifest, null for installs made before manifests existed. */
+  os: string | null;
+  is_launcher: boolean | null;
+  template_version: string | null;
+  template_hash: string | null;
+  last_launched: number | null;
+}
+
+export interface GameManifest {
+  app_id: number;
+  name: string;
+  created: number;
+  executables: {
+    name: string;
+    os: string;
+    is_launcher: boolean;
+    template_version: string;
+    template_hash: string;
+    installed: number;
+  }[];
+  launches: {
+    executable: string;
+    pid: number | null;
+    started: number;
+    ended: number | null;
+    exit_code: number | null;
+  }[];
+}
+
+export interface InstalledGame {
+  app_id: number;
+  path: string;
+  executables: InstalledExecutable[];
+  manifest: GameManifest | null;
+}